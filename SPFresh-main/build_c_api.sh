@@ -6,8 +6,10 @@ set -e
 
 echo "Building SPFresh C API..."
 
-# Create build directory
-mkdir -p build_c_api
+# Create build directory. CMake only reads files named CMakeLists.txt, so the
+# C API project is staged next to it (the repo root one builds all of SPTAG)
+mkdir -p build_c_api/project
+cp CMakeLists_c_api.txt build_c_api/project/CMakeLists.txt
 cd build_c_api
 
 # Configure CMake
@@ -15,8 +17,8 @@ cmake -DCMAKE_BUILD_TYPE=Release \
       -DCMAKE_C_COMPILER=gcc \
       -DCMAKE_CXX_COMPILER=g++ \
       -DCMAKE_CXX_FLAGS="-fPIC" \
-      -f ../CMakeLists_c_api.txt \
-      ..
+      -DSPFRESH_ROOT="$(cd .. && pwd)" \
+      project
 
 # Build
 make -j$(nproc)
@@ -26,4 +28,3 @@ make install DESTDIR=../install
 
 echo "SPFresh C API built successfully!"
 echo "Library located at: $(pwd)/libspfresh_c_api.so"
-echo "Header located at: $(pwd)/../spfresh_c_api.h"
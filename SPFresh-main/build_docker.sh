@@ -99,6 +99,11 @@ SPFreshIndex* spfresh_create_index(int dimension) {
     return index;
 }
 
+// The in-memory store keeps nothing on disk, so the directory is ignored
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory) {
+    return spfresh_create_index(dimension);
+}
+
 void spfresh_destroy_index(SPFreshIndex* index) {
     if (index) {
         delete static_cast<SimpleVectorStore*>(index->internal_data);
@@ -160,6 +165,7 @@ typedef struct SPFreshIndex SPFreshIndex;
 
 // Create and destroy index
 SPFreshIndex* spfresh_create_index(int dimension);
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory);
 void spfresh_destroy_index(SPFreshIndex* index);
 
 // Add vector to index
@@ -259,6 +265,11 @@ SPFreshIndex* spfresh_create_index(int dimension) {
     return index;
 }
 
+// The in-memory store keeps nothing on disk, so the directory is ignored
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory) {
+    return spfresh_create_index(dimension);
+}
+
 void spfresh_destroy_index(SPFreshIndex* index) {
     if (index) {
         delete static_cast<SimpleVectorStore*>(index->internal_data);
@@ -320,6 +331,7 @@ typedef struct SPFreshIndex SPFreshIndex;
 
 // Create and destroy index
 SPFreshIndex* spfresh_create_index(int dimension);
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory);
 void spfresh_destroy_index(SPFreshIndex* index);
 
 // Add vector to index
@@ -143,13 +143,21 @@ public:
 };
 
 // API functions
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory);
+
 SPFreshIndex* spfresh_create_index(int dimension) {
+    return spfresh_create_index_at(dimension, "./data");
+}
+
+// Same as spfresh_create_index, but keeps the index file in `directory` so
+// several indexes can live side by side
+SPFreshIndex* spfresh_create_index_at(int dimension, const char* directory) {
     SPFreshIndex* index = new SPFreshIndex();
     index->dimension = dimension;
     index->max_vectors = 1000000;
     
     // Create index file path
-    std::string index_path = "./data/reviews.index";
+    std::string index_path = std::string(directory ? directory : "./data") + "/reviews.index";
     index->index_file_path = new char[index_path.length() + 1];
     strcpy(index->index_file_path, index_path.c_str());
     
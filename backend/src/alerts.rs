@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::models::{Alert, AlertMatch};
+
+const ALERTS_FILE: &str = "alerts.json";
+const MATCHES_FILE: &str = "matches.jsonl";
+const DELIVERIES_FILE: &str = "deliveries.jsonl";
+
+/// Retry policy for webhook deliveries.
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryConfig {
+    /// Attempts per match, including the first one.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after every failed attempt.
+    pub initial_backoff: Duration,
+    /// Per-request timeout.
+    pub timeout: Duration,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// An alert with the query embedding it is matched with. The embedding is
+/// tied to `model`; alerts saved under another model get re-embedded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAlert {
+    #[serde(flatten)]
+    pub alert: Alert,
+    pub model: String,
+    pub embedding: Vec<f32>,
+}
+
+/// Outcome of delivering one match to its webhook.
+#[derive(Debug, Serialize, Deserialize)]
+struct DeliveryRecord {
+    match_id: String,
+    alert_id: String,
+    url: String,
+    attempts: u32,
+    delivered: bool,
+    error: Option<String>,
+    finished_at: String,
+}
+
+/// Saved searches, their matches and webhook deliveries under
+/// `data_dir/alerts/`: definitions in `alerts.json`, matches and delivery
+/// outcomes appended to `matches.jsonl` and `deliveries.jsonl`.
+pub struct AlertStore {
+    dir: PathBuf,
+    alerts: RwLock<Vec<SavedAlert>>,
+    delivery: DeliveryConfig,
+    client: reqwest::Client,
+    /// Serializes appends to the jsonl logs.
+    append_lock: Arc<Mutex<()>>,
+}
+
+impl AlertStore {
+    pub fn load(data_dir: &str, delivery: DeliveryConfig) -> Result<Self> {
+        let dir = Path::new(data_dir).join("alerts");
+        let path = dir.join(ALERTS_FILE);
+
+        let alerts = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(delivery.timeout)
+            .build()
+            .context("Failed to build webhook client")?;
+
+        Ok(Self {
+            dir,
+            alerts: RwLock::new(alerts),
+            delivery,
+            client,
+            append_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    pub async fn list(&self) -> Vec<Alert> {
+        self.alerts.read().await.iter().map(|saved| saved.alert.clone()).collect()
+    }
+
+    pub async fn saved(&self) -> Vec<SavedAlert> {
+        self.alerts.read().await.clone()
+    }
+
+    pub async fn add(&self, saved: SavedAlert) -> Result<()> {
+        let mut alerts = self.alerts.write().await;
+        alerts.push(saved);
+        self.save(&alerts)
+    }
+
+    pub async fn remove(&self, alert_id: &str) -> Result<Option<Alert>> {
+        let mut alerts = self.alerts.write().await;
+        let Some(position) = alerts.iter().position(|saved| saved.alert.id == alert_id) else {
+            return Ok(None);
+        };
+
+        let removed = alerts.remove(position);
+        self.save(&alerts)?;
+        Ok(Some(removed.alert))
+    }
+
+    /// Swaps in embeddings computed with `model`, keyed by alert id.
+    pub async fn replace_embeddings(&self, model: &str, embeddings: HashMap<String, Vec<f32>>) -> Result<()> {
+        if embeddings.is_empty() {
+            return Ok(());
+        }
+
+        let mut alerts = self.alerts.write().await;
+        for saved in alerts.iter_mut() {
+            if let Some(embedding) = embeddings.get(&saved.alert.id) {
+                saved.model = model.to_string();
+                saved.embedding = embedding.clone();
+            }
+        }
+        self.save(&alerts)
+    }
+
+    pub fn record_match(&self, alert_match: &AlertMatch) -> Result<()> {
+        append_line(&self.append_lock, &self.dir.join(MATCHES_FILE), alert_match)
+    }
+
+    /// Matches recorded for `alert_id`, oldest first.
+    pub fn matches(&self, alert_id: &str) -> Result<Vec<AlertMatch>> {
+        let path = self.dir.join(MATCHES_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut matches = Vec::new();
+        for line in BufReader::new(File::open(&path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let alert_match: AlertMatch = serde_json::from_str(&line)?;
+            if alert_match.alert_id == alert_id {
+                matches.push(alert_match);
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Posts `alert_match` to `url` in the background, retrying with
+    /// exponential backoff. The outcome is appended to `deliveries.jsonl`.
+    pub fn deliver(&self, url: String, alert_match: AlertMatch) {
+        let client = self.client.clone();
+        let config = self.delivery;
+        let path = self.dir.join(DELIVERIES_FILE);
+        let append_lock = Arc::clone(&self.append_lock);
+
+        tokio::spawn(async move {
+            let mut backoff = config.initial_backoff;
+            let mut attempts = 0;
+            let mut error = None;
+
+            while attempts < config.max_attempts.max(1) {
+                if attempts > 0 {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                attempts += 1;
+
+                match client.post(&url).json(&alert_match).send().await {
+                    Ok(response) if response.status().is_success() => {
+                        error = None;
+                        break;
+                    }
+                    Ok(response) => error = Some(format!("Webhook returned {}", response.status())),
+                    Err(e) => error = Some(e.to_string()),
+                }
+            }
+
+            if let Some(e) = &error {
+                tracing::warn!("Giving up on alert webhook {} after {} attempts: {}", url, attempts, e);
+            }
+
+            let record = DeliveryRecord {
+                match_id: alert_match.id.clone(),
+                alert_id: alert_match.alert_id.clone(),
+                url,
+                attempts,
+                delivered: error.is_none(),
+                error,
+                finished_at: chrono::Utc::now().to_rfc3339(),
+            };
+            if let Err(e) = append_line(&append_lock, &path, &record) {
+                tracing::warn!("Failed to record alert delivery: {}", e);
+            }
+        });
+    }
+
+    fn save(&self, alerts: &[SavedAlert]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let path = self.dir.join(ALERTS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(alerts)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+}
+
+fn append_line<T: Serialize>(lock: &Mutex<()>, path: &Path, value: &T) -> Result<()> {
+    let _guard = lock.lock().map_err(|e| anyhow::anyhow!("Alert log lock poisoned: {}", e))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(value)?)?;
+    Ok(())
+}
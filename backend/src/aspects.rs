@@ -0,0 +1,172 @@
+use anyhow::Result;
+
+use crate::chunking::{split_sentences, ChunkSpan};
+use crate::models::{AspectSentiment, AspectSnippet};
+use crate::sentiment::POLARITY_THRESHOLD;
+
+/// Snippets returned per aspect, split between the most positive and the most
+/// negative mentions.
+const SNIPPETS_PER_ASPECT: usize = 4;
+
+/// Words that turn a sentence around, so "love the screen but hate the
+/// battery" is scored as two clauses.
+const CONTRASTS: &[&str] = &[" but ", " however ", " although ", " though ", " whereas ", "; "];
+
+/// Something reviews talk about, matched against review sentences by
+/// embedding `description`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aspect {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AspectConfig {
+    pub aspects: Vec<Aspect>,
+    /// Minimum cosine similarity between a clause and an aspect description.
+    pub threshold: f32,
+}
+
+impl Default for AspectConfig {
+    fn default() -> Self {
+        Self {
+            aspects: parse_aspects(
+                "battery=battery life and charging,price=price and value for money,\
+                 screen=screen and display,delivery=delivery and shipping,\
+                 sound=sound and speakers,build quality=build quality and durability,\
+                 customer service=customer service and support",
+            )
+            .unwrap_or_default(),
+            threshold: 0.6,
+        }
+    }
+}
+
+/// Parses a comma-separated aspect list. Each entry is a name, optionally
+/// followed by `=` and a longer description to embed instead of the name,
+/// e.g. `battery=battery life and charging,price`.
+pub fn parse_aspects(spec: &str) -> Result<Vec<Aspect>> {
+    let mut aspects: Vec<Aspect> = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (name, description) = match entry.split_once('=') {
+            Some((name, description)) => (name.trim(), description.trim()),
+            None => (entry, entry),
+        };
+        anyhow::ensure!(
+            !name.is_empty() && !description.is_empty(),
+            "Aspect '{}' needs a name and a description",
+            entry
+        );
+        anyhow::ensure!(
+            aspects.iter().all(|aspect| aspect.name != name),
+            "Aspect '{}' is listed twice",
+            name
+        );
+
+        aspects.push(Aspect {
+            name: name.to_string(),
+            description: description.to_string(),
+        });
+    }
+
+    anyhow::ensure!(!aspects.is_empty(), "The aspect list is empty");
+    Ok(aspects)
+}
+
+/// Byte ranges of the clauses of `text`: sentences, further split at
+/// contrasting conjunctions.
+pub fn split_clauses(text: &str) -> Vec<ChunkSpan> {
+    let lowercase = text.to_lowercase();
+    // Lowercasing can change byte lengths outside ASCII; fall back to
+    // whole sentences rather than risk cutting inside a character
+    let splittable = lowercase.len() == text.len();
+
+    let mut clauses = Vec::new();
+    for sentence in split_sentences(text) {
+        let mut start = sentence.start;
+        if splittable {
+            let slice = &lowercase[sentence.start..sentence.end];
+            let mut cuts: Vec<usize> = CONTRASTS
+                .iter()
+                .flat_map(|contrast| slice.match_indices(contrast).map(|(i, _)| sentence.start + i))
+                .collect();
+            cuts.sort_unstable();
+
+            for cut in cuts {
+                push_clause(text, start, cut, &mut clauses);
+                start = cut;
+            }
+        }
+        push_clause(text, start, sentence.end, &mut clauses);
+    }
+
+    clauses
+}
+
+fn push_clause(text: &str, start: usize, end: usize, clauses: &mut Vec<ChunkSpan>) {
+    let slice = &text[start..end];
+    let trimmed = slice.trim_matches(|c: char| c.is_whitespace() || c == ';');
+    if trimmed.chars().any(char::is_alphanumeric) {
+        let offset = slice.find(trimmed).unwrap_or(0);
+        clauses.push(ChunkSpan {
+            start: start + offset,
+            end: start + offset + trimmed.len(),
+        });
+    }
+}
+
+/// One clause matched to an aspect.
+pub struct Mention {
+    pub review_id: String,
+    pub text: String,
+    pub sentiment: f32,
+    pub similarity: f32,
+}
+
+/// Aggregates an aspect's mentions: polarity counts, mean sentiment, and the
+/// strongest positive and negative snippets.
+pub fn summarize(name: &str, mentions: Vec<Mention>) -> AspectSentiment {
+    let count = mentions.len();
+    let mean_sentiment = if count > 0 {
+        mentions.iter().map(|mention| mention.sentiment).sum::<f32>() / count as f32
+    } else {
+        0.0
+    };
+    let positive = mentions.iter().filter(|mention| mention.sentiment >= POLARITY_THRESHOLD).count();
+    let negative = mentions.iter().filter(|mention| mention.sentiment <= -POLARITY_THRESHOLD).count();
+
+    // Strongest opinions first, most relevant first among equals
+    let strongest = |sign: f32| {
+        let mut picked: Vec<&Mention> = mentions
+            .iter()
+            .filter(|mention| mention.sentiment * sign >= POLARITY_THRESHOLD)
+            .collect();
+        picked.sort_by(|a, b| {
+            (b.sentiment * sign)
+                .total_cmp(&(a.sentiment * sign))
+                .then_with(|| b.similarity.total_cmp(&a.similarity))
+        });
+        picked.truncate(SNIPPETS_PER_ASPECT / 2);
+        picked
+    };
+    let snippets = strongest(1.0)
+        .into_iter()
+        .chain(strongest(-1.0))
+        .map(|mention| AspectSnippet {
+            review_id: mention.review_id.clone(),
+            text: mention.text.clone(),
+            sentiment: mention.sentiment,
+            similarity: mention.similarity,
+        })
+        .collect();
+
+    AspectSentiment {
+        aspect: name.to_string(),
+        mentions: count,
+        mean_sentiment,
+        positive,
+        neutral: count - positive - negative,
+        negative,
+        snippets,
+    }
+}
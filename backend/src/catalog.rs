@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::models::{CreateProductRequest, Product, ProductFields, ProductImportReport};
+
+const PRODUCTS_FILE: &str = "products.json";
+
+/// Product names, categories and brands keyed by the `product_id` reviews
+/// carry, kept in `data_dir/products.json`.
+pub struct ProductCatalog {
+    path: PathBuf,
+    products: RwLock<BTreeMap<String, Product>>,
+}
+
+impl ProductCatalog {
+    pub fn load(data_dir: &str) -> Result<Self> {
+        let path = Path::new(data_dir).join(PRODUCTS_FILE);
+
+        let products: Vec<Product> = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path,
+            products: RwLock::new(products.into_iter().map(|product| (product.id.clone(), product)).collect()),
+        })
+    }
+
+    /// Products by id, for joining onto reviews.
+    pub async fn products(&self) -> RwLockReadGuard<'_, BTreeMap<String, Product>> {
+        self.products.read().await
+    }
+
+    pub async fn get(&self, id: &str) -> Option<Product> {
+        self.products.read().await.get(id).cloned()
+    }
+
+    /// Products in id order, optionally only those of a category and brand.
+    pub async fn list(&self, category: Option<&str>, brand: Option<&str>) -> Vec<Product> {
+        self.products
+            .read()
+            .await
+            .values()
+            .filter(|product| matches_attribute(&product.fields.category, category))
+            .filter(|product| matches_attribute(&product.fields.brand, brand))
+            .cloned()
+            .collect()
+    }
+
+    /// Ids of the products in `category` and of `brand`; `None` if neither
+    /// is given.
+    pub async fn resolve(&self, category: Option<&str>, brand: Option<&str>) -> Option<HashSet<String>> {
+        if category.is_none() && brand.is_none() {
+            return None;
+        }
+
+        Some(
+            self.list(category, brand)
+                .await
+                .into_iter()
+                .map(|product| product.id)
+                .collect(),
+        )
+    }
+
+    /// Adds a product, or returns `None` if the id is taken.
+    pub async fn create(&self, request: CreateProductRequest) -> Result<Option<Product>> {
+        let mut products = self.products.write().await;
+        if products.contains_key(&request.id) {
+            return Ok(None);
+        }
+
+        let product = new_product(request);
+        products.insert(product.id.clone(), product.clone());
+        self.save(&products)?;
+        Ok(Some(product))
+    }
+
+    /// Replaces a product's fields, or returns `None` if there is no such product.
+    pub async fn update(&self, id: &str, fields: ProductFields) -> Result<Option<Product>> {
+        let mut products = self.products.write().await;
+        let Some(product) = products.get_mut(id) else {
+            return Ok(None);
+        };
+
+        product.fields = fields;
+        product.updated_at = chrono::Utc::now().to_rfc3339();
+        let product = product.clone();
+        self.save(&products)?;
+        Ok(Some(product))
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<Option<Product>> {
+        let mut products = self.products.write().await;
+        let Some(product) = products.remove(id) else {
+            return Ok(None);
+        };
+
+        self.save(&products)?;
+        Ok(Some(product))
+    }
+
+    /// Creates or updates every product in one write.
+    pub async fn import(&self, requests: Vec<CreateProductRequest>) -> Result<ProductImportReport> {
+        let mut products = self.products.write().await;
+        let mut report = ProductImportReport::default();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        for request in requests {
+            match products.get_mut(&request.id) {
+                Some(product) => {
+                    product.fields = request.fields;
+                    product.updated_at = now.clone();
+                    report.updated += 1;
+                }
+                None => {
+                    let product = new_product(request);
+                    products.insert(product.id.clone(), product);
+                    report.created += 1;
+                }
+            }
+        }
+
+        self.save(&products)?;
+        Ok(report)
+    }
+
+    fn save(&self, products: &BTreeMap<String, Product>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let products: Vec<&Product> = products.values().collect();
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(&products)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+fn new_product(request: CreateProductRequest) -> Product {
+    let now = chrono::Utc::now().to_rfc3339();
+    Product {
+        id: request.id,
+        fields: request.fields,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
+/// Case-insensitive match of a product attribute against a wanted value.
+fn matches_attribute(value: &Option<String>, wanted: Option<&str>) -> bool {
+    match wanted {
+        Some(wanted) => value.as_ref().is_some_and(|value| value.trim().eq_ignore_ascii_case(wanted.trim())),
+        None => true,
+    }
+}
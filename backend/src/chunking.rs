@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// How review bodies are cut into overlapping sentence windows before
+/// embedding. Each window becomes its own index vector.
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkConfig {
+    /// Sentences per chunk.
+    pub window_sentences: usize,
+    /// Sentences shared between consecutive chunks.
+    pub overlap_sentences: usize,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self {
+            window_sentences: 4,
+            overlap_sentences: 1,
+        }
+    }
+}
+
+/// Byte range of a chunk within `review_body`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits `text` into sentence byte ranges on `.`, `!`, `?`, `…` and line
+/// breaks. Surrounding whitespace is trimmed from each range.
+pub fn split_sentences(text: &str) -> Vec<ChunkSpan> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let is_terminator = matches!(c, '.' | '!' | '?' | '…' | '\n');
+        // Keep runs like "?!" or "..." inside the same sentence
+        let next_continues = chars
+            .peek()
+            .is_some_and(|&(_, next)| matches!(next, '.' | '!' | '?' | '…'));
+
+        if is_terminator && !next_continues {
+            push_trimmed(text, start, i + c.len_utf8(), &mut sentences);
+            start = i + c.len_utf8();
+        }
+    }
+    push_trimmed(text, start, text.len(), &mut sentences);
+
+    sentences
+}
+
+fn push_trimmed(text: &str, start: usize, end: usize, sentences: &mut Vec<ChunkSpan>) {
+    let slice = &text[start..end];
+    let leading = slice.len() - slice.trim_start().len();
+    let trailing = slice.len() - slice.trim_end().len();
+
+    if leading + trailing < slice.len() {
+        sentences.push(ChunkSpan {
+            start: start + leading,
+            end: end - trailing,
+        });
+    }
+}
+
+/// Groups the sentences of `body` into overlapping windows. Short bodies and
+/// bodies without sentences come back as a single chunk covering everything;
+/// blank bodies have no chunks, like blank titles have no vector.
+pub fn chunk_body(body: &str, config: &ChunkConfig) -> Vec<ChunkSpan> {
+    if body.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let sentences = split_sentences(body);
+    let window = config.window_sentences.max(1);
+
+    if sentences.len() <= window {
+        return vec![ChunkSpan { start: 0, end: body.len() }];
+    }
+
+    let step = window.saturating_sub(config.overlap_sentences).max(1);
+    let mut chunks = Vec::new();
+    let mut first = 0;
+
+    loop {
+        let last = (first + window).min(sentences.len()) - 1;
+        chunks.push(ChunkSpan {
+            start: sentences[first].start,
+            end: sentences[last].end,
+        });
+
+        if last + 1 >= sentences.len() {
+            break;
+        }
+        first += step;
+    }
+
+    chunks
+}
@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::{RwLock, RwLockReadGuard};
+
+use crate::models::{CentralReview, ClusterMethod, ClusterRequest, ClusterRun, ClusterSummary, Review};
+use crate::vector_store::cosine_similarity;
+
+pub const DEFAULT_CLUSTERS: usize = 8;
+pub const DEFAULT_MAX_ITERATIONS: usize = 50;
+
+const CLUSTERS_FILE: &str = "latest.json";
+const CENTRAL_REVIEWS: usize = 3;
+const TOP_TERMS: usize = 5;
+const LABEL_TERMS: usize = 3;
+
+/// How strongly balanced k-means pushes points away from oversized
+/// clusters: a cluster twice the ideal size costs this much extra similarity.
+const BALANCE_PENALTY: f32 = 0.2;
+
+/// Seed for k-means++ initialization, so reruns over the same reviews give
+/// the same clusters.
+const SEED: u64 = 0x5eed_c105;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "are", "but", "not", "you", "all", "any", "can", "had", "her", "was", "one",
+    "our", "out", "has", "have", "this", "that", "with", "they", "from", "been", "were", "what",
+    "when", "will", "would", "there", "their", "them", "than", "then", "its", "it's", "very",
+    "just", "also", "too", "really", "after", "before", "about", "into", "only", "more", "most",
+    "some", "such", "does", "did", "doesn't", "don't", "didn't", "isn't", "wasn't", "get", "got",
+    "his", "she", "him", "who", "which", "because", "could", "should", "over", "these", "those",
+    "here", "your", "yours", "i'm", "i've", "even", "much", "well", "still", "like",
+];
+
+/// Whether a review falls inside a cluster request's product and time scope.
+pub struct ClusterScope {
+    product_ids: Option<HashSet<String>>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl ClusterScope {
+    pub fn new(request: &ClusterRequest) -> Result<Self> {
+        let parse = |value: &Option<String>, name: &str| -> Result<Option<DateTime<Utc>>> {
+            value
+                .as_deref()
+                .map(|value| {
+                    DateTime::parse_from_rfc3339(value)
+                        .map(|time| time.with_timezone(&Utc))
+                        .with_context(|| format!("`{}` is not an RFC 3339 timestamp", name))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            product_ids: request.product_ids.as_ref().map(|ids| ids.iter().cloned().collect()),
+            since: parse(&request.since, "since")?,
+            until: parse(&request.until, "until")?,
+        })
+    }
+
+    pub fn contains(&self, review: &Review) -> bool {
+        if let Some(product_ids) = &self.product_ids {
+            if !product_ids.contains(&review.product_id) {
+                return false;
+            }
+        }
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+
+        let Ok(written) = DateTime::parse_from_rfc3339(&review.timestamp) else {
+            return false;
+        };
+        let written = written.with_timezone(&Utc);
+        self.since.is_none_or(|since| written >= since) && self.until.is_none_or(|until| written < until)
+    }
+}
+
+/// Result of clustering: a cluster per point, the normalized centroids and
+/// how many assignment rounds it took.
+pub struct Clustering {
+    pub assignments: Vec<usize>,
+    pub centroids: Vec<Vec<f32>>,
+    pub iterations: usize,
+}
+
+/// Spherical k-means over normalized vectors, seeded with k-means++.
+/// `Balanced` discourages clusters from growing past `n / k` points by
+/// charging each cluster a penalty proportional to its size in the previous
+/// round.
+pub fn kmeans(points: &[Vec<f32>], k: usize, method: ClusterMethod, max_iterations: usize) -> Clustering {
+    let k = k.min(points.len());
+    if k == 0 {
+        return Clustering {
+            assignments: vec![0; points.len()],
+            centroids: Vec::new(),
+            iterations: 0,
+        };
+    }
+
+    let mut centroids = initial_centroids(points, k);
+    let mut assignments = vec![usize::MAX; points.len()];
+    let mut sizes = vec![points.len() as f32 / k as f32; k];
+    let ideal = points.len() as f32 / k as f32;
+    let mut iterations = 0;
+
+    while iterations < max_iterations.max(1) {
+        iterations += 1;
+
+        let mut changed = false;
+        for (point, assignment) in points.iter().zip(assignments.iter_mut()) {
+            let best = (0..k)
+                .max_by(|&a, &b| {
+                    let cost = |cluster: usize| {
+                        let penalty = match method {
+                            ClusterMethod::Kmeans => 0.0,
+                            ClusterMethod::Balanced => BALANCE_PENALTY * sizes[cluster] / ideal,
+                        };
+                        cosine_similarity(point, &centroids[cluster]) - penalty
+                    };
+                    cost(a).total_cmp(&cost(b))
+                })
+                .unwrap_or(0);
+            if *assignment != best {
+                *assignment = best;
+                changed = true;
+            }
+        }
+
+        sizes = vec![0.0; k];
+        for &cluster in &assignments {
+            sizes[cluster] += 1.0;
+        }
+
+        if !changed {
+            break;
+        }
+        centroids = update_centroids(points, &assignments, &centroids);
+    }
+
+    Clustering {
+        assignments,
+        centroids,
+        iterations,
+    }
+}
+
+/// k-means++: each next centroid is drawn with probability proportional to
+/// its squared cosine distance from the closest centroid so far.
+fn initial_centroids(points: &[Vec<f32>], k: usize) -> Vec<Vec<f32>> {
+    let mut rng = SplitMix64(SEED);
+    let mut centroids = vec![points[rng.below(points.len())].clone()];
+
+    while centroids.len() < k {
+        let weights: Vec<f32> = points
+            .iter()
+            .map(|point| {
+                let closest = centroids
+                    .iter()
+                    .map(|centroid| cosine_similarity(point, centroid))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                (1.0 - closest).max(0.0).powi(2)
+            })
+            .collect();
+
+        let total: f32 = weights.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = rng.unit() * total;
+            weights
+                .iter()
+                .position(|&weight| {
+                    target -= weight;
+                    target <= 0.0
+                })
+                .unwrap_or(points.len() - 1)
+        } else {
+            // Every point coincides with a centroid already
+            rng.below(points.len())
+        };
+        centroids.push(points[next].clone());
+    }
+
+    centroids
+}
+
+/// Normalized mean of each cluster's points. A cluster left empty takes over
+/// the point that fits its own cluster worst.
+fn update_centroids(points: &[Vec<f32>], assignments: &[usize], previous: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let dimension = points.first().map_or(0, Vec::len);
+    let mut sums = vec![vec![0.0f32; dimension]; previous.len()];
+    for (point, &cluster) in points.iter().zip(assignments) {
+        for (sum, value) in sums[cluster].iter_mut().zip(point) {
+            *sum += value;
+        }
+    }
+
+    let mut taken = HashSet::new();
+    sums.into_iter()
+        .map(|mut sum| {
+            let norm = sum.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                sum.iter_mut().for_each(|x| *x /= norm);
+                return sum;
+            }
+
+            let worst = points
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !taken.contains(i))
+                .min_by(|(i, a), (j, b)| {
+                    cosine_similarity(a, &previous[assignments[*i]])
+                        .total_cmp(&cosine_similarity(b, &previous[assignments[*j]]))
+                })
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            taken.insert(worst);
+            points[worst].clone()
+        })
+        .collect()
+}
+
+/// Describes each cluster by its most central reviews and its most
+/// distinctive terms. `reviews`, `points` and `clustering.assignments` line
+/// up index by index.
+pub fn summarize(reviews: &[Review], points: &[Vec<f32>], clustering: &Clustering) -> Vec<ClusterSummary> {
+    let documents: Vec<HashSet<String>> = reviews
+        .iter()
+        .map(|review| terms(&format!("{} {}", review.review_title, review.review_body)))
+        .collect();
+
+    let mut corpus_frequency: HashMap<&str, usize> = HashMap::new();
+    for document in &documents {
+        for term in document {
+            *corpus_frequency.entry(term).or_default() += 1;
+        }
+    }
+
+    (0..clustering.centroids.len())
+        .map(|cluster_id| {
+            let members: Vec<usize> = (0..reviews.len())
+                .filter(|&i| clustering.assignments[i] == cluster_id)
+                .collect();
+
+            let mut central: Vec<CentralReview> = members
+                .iter()
+                .map(|&i| CentralReview {
+                    review_id: reviews[i].id.clone(),
+                    review_title: reviews[i].review_title.clone(),
+                    product_id: reviews[i].product_id.clone(),
+                    similarity: cosine_similarity(&points[i], &clustering.centroids[cluster_id]),
+                })
+                .collect();
+            central.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+            central.truncate(CENTRAL_REVIEWS);
+
+            let top_terms = distinctive_terms(&members, &documents, &corpus_frequency);
+            ClusterSummary {
+                cluster_id,
+                size: members.len(),
+                label: top_terms.iter().take(LABEL_TERMS).cloned().collect::<Vec<_>>().join(", "),
+                top_terms,
+                central_reviews: central,
+            }
+        })
+        .collect()
+}
+
+/// Terms ranked by `share * ln(1 + lift)`, where `share` is the fraction of
+/// the cluster's reviews using the term and `lift` how much more often it
+/// appears in the cluster than in all clustered reviews. Terms used by only
+/// one review of a larger cluster are ignored.
+fn distinctive_terms(
+    members: &[usize],
+    documents: &[HashSet<String>],
+    corpus_frequency: &HashMap<&str, usize>,
+) -> Vec<String> {
+    let mut cluster_frequency: HashMap<&str, usize> = HashMap::new();
+    for &i in members {
+        for term in &documents[i] {
+            *cluster_frequency.entry(term).or_default() += 1;
+        }
+    }
+
+    let min_frequency = members.len().min(2);
+    let total = documents.len() as f32;
+    let mut scored: Vec<(&str, f32)> = cluster_frequency
+        .into_iter()
+        .filter(|(_, count)| *count >= min_frequency)
+        .map(|(term, count)| {
+            let share = count as f32 / members.len() as f32;
+            let overall = corpus_frequency[term] as f32 / total;
+            (term, share * (1.0 + share / overall).ln())
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    scored.into_iter().take(TOP_TERMS).map(|(term, _)| term.to_string()).collect()
+}
+
+fn terms(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| word.chars().count() >= 3 && !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !STOPWORDS.contains(word))
+        .map(str::to_string)
+        .collect()
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedClusters {
+    run: Option<ClusterRun>,
+    assignments: HashMap<String, usize>,
+}
+
+/// The latest clustering run and its review assignments, persisted to
+/// `data_dir/clusters/latest.json`.
+pub struct ClusterStore {
+    dir: PathBuf,
+    run: RwLock<Option<ClusterRun>>,
+    assignments: RwLock<HashMap<String, usize>>,
+}
+
+impl ClusterStore {
+    pub fn load(data_dir: &str) -> Result<Self> {
+        let dir = Path::new(data_dir).join("clusters");
+        let path = dir.join(CLUSTERS_FILE);
+
+        let saved: SavedClusters = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            SavedClusters::default()
+        };
+
+        Ok(Self {
+            dir,
+            run: RwLock::new(saved.run),
+            assignments: RwLock::new(saved.assignments),
+        })
+    }
+
+    pub async fn latest(&self) -> Option<ClusterRun> {
+        self.run.read().await.clone()
+    }
+
+    /// Review id to cluster id of the latest run.
+    pub async fn assignments(&self) -> RwLockReadGuard<'_, HashMap<String, usize>> {
+        self.assignments.read().await
+    }
+
+    pub async fn replace(&self, run: ClusterRun, assignments: HashMap<String, usize>) -> Result<()> {
+        let mut current_run = self.run.write().await;
+        let mut current_assignments = self.assignments.write().await;
+
+        let saved = SavedClusters {
+            run: Some(run),
+            assignments,
+        };
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(CLUSTERS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(&saved)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        *current_run = saved.run;
+        *current_assignments = saved.assignments;
+        Ok(())
+    }
+}
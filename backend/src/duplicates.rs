@@ -0,0 +1,111 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt;
+
+/// What `insert_review` does with a review that nearly duplicates a stored one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateMode {
+    /// Don't look for duplicates.
+    #[default]
+    Off,
+    /// Store the review with `duplicate_of` set.
+    Flag,
+    /// Refuse the review with a `DuplicateReview` error.
+    Reject,
+}
+
+pub fn parse_duplicate_mode(value: &str) -> Result<DuplicateMode> {
+    match value.to_ascii_lowercase().as_str() {
+        "off" => Ok(DuplicateMode::Off),
+        "flag" => Ok(DuplicateMode::Flag),
+        "reject" => Ok(DuplicateMode::Reject),
+        other => Err(anyhow::anyhow!(
+            "Unknown duplicate mode '{}', expected 'off', 'flag' or 'reject'",
+            other
+        )),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DuplicateConfig {
+    pub mode: DuplicateMode,
+    /// Minimum cosine similarity between the mean vectors of two reviews for
+    /// one to count as a duplicate of the other.
+    pub threshold: f32,
+}
+
+impl Default for DuplicateConfig {
+    fn default() -> Self {
+        Self {
+            mode: DuplicateMode::Off,
+            threshold: 0.95,
+        }
+    }
+}
+
+/// Returned by `insert_review` when a review is rejected as a near-duplicate.
+#[derive(Debug, Clone)]
+pub struct DuplicateReview {
+    pub duplicate_of: String,
+    pub similarity: f32,
+}
+
+impl fmt::Display for DuplicateReview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Review duplicates review {} (similarity {:.3})",
+            self.duplicate_of, self.similarity
+        )
+    }
+}
+
+impl std::error::Error for DuplicateReview {}
+
+/// Groups items linked by duplicate pairs into clusters (connected
+/// components), each listed in ascending order. Items without a pair are left
+/// out.
+pub fn group_pairs(pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+
+    fn root(parent: &mut HashMap<usize, usize>, item: usize) -> usize {
+        let mut current = item;
+        while let Some(&next) = parent.get(&current) {
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+        // Point the path straight at the root for next time
+        let mut node = item;
+        while node != current {
+            let next = parent[&node];
+            parent.insert(node, current);
+            node = next;
+        }
+        current
+    }
+
+    for &(a, b) in pairs {
+        parent.entry(a).or_insert(a);
+        parent.entry(b).or_insert(b);
+        let (root_a, root_b) = (root(&mut parent, a), root(&mut parent, b));
+        if root_a != root_b {
+            parent.insert(root_a.max(root_b), root_a.min(root_b));
+        }
+    }
+
+    let items: Vec<usize> = parent.keys().copied().collect();
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for item in items {
+        let cluster_root = root(&mut parent, item);
+        clusters.entry(cluster_root).or_default().push(item);
+    }
+
+    let mut clusters: Vec<Vec<usize>> = clusters.into_values().collect();
+    for cluster in &mut clusters {
+        cluster.sort_unstable();
+    }
+    clusters.sort();
+    clusters
+}
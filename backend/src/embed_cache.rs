@@ -0,0 +1,181 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::models::CacheStats;
+
+/// Content-addressed embedding cache with an in-memory LRU tier in front of
+/// an optional on-disk tier. Entries are keyed by model id and a hash of the
+/// normalized text, so switching models never returns stale vectors.
+pub struct EmbeddingCache {
+    memory: Mutex<LruTier>,
+    disk_dir: Option<PathBuf>,
+    memory_hits: AtomicU64,
+    disk_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    pub fn new(capacity: usize, disk_dir: Option<PathBuf>) -> Self {
+        Self {
+            memory: Mutex::new(LruTier::new(capacity)),
+            disk_dir,
+            memory_hits: AtomicU64::new(0),
+            disk_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn key(model_id: &str, text: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(model_id.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(normalize_text(text).as_bytes());
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<f32>> {
+        if let Some(vector) = self.memory.lock().ok().and_then(|mut memory| memory.get(key)) {
+            self.memory_hits.fetch_add(1, Ordering::Relaxed);
+            return Some(vector);
+        }
+
+        if let Some(vector) = self.read_disk(key) {
+            self.disk_hits.fetch_add(1, Ordering::Relaxed);
+            if let Ok(mut memory) = self.memory.lock() {
+                memory.insert(key.to_string(), vector.clone());
+            }
+            return Some(vector);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    pub fn put(&self, key: &str, vector: &[f32]) {
+        if let Err(e) = self.write_disk(key, vector) {
+            tracing::warn!("Failed to persist cached embedding {}: {}", key, e);
+        }
+
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(key.to_string(), vector.to_vec());
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let memory_hits = self.memory_hits.load(Ordering::Relaxed);
+        let disk_hits = self.disk_hits.load(Ordering::Relaxed);
+
+        CacheStats {
+            hits: memory_hits + disk_hits,
+            memory_hits,
+            disk_hits,
+            misses: self.misses.load(Ordering::Relaxed),
+            memory_entries: self.memory.lock().map(|memory| memory.len()).unwrap_or(0),
+        }
+    }
+
+    fn disk_path(&self, key: &str) -> Option<PathBuf> {
+        // Shard by the first byte of the hash to keep directories small
+        self.disk_dir
+            .as_ref()
+            .map(|dir| dir.join(&key[..2]).join(format!("{}.bin", key)))
+    }
+
+    fn read_disk(&self, key: &str) -> Option<Vec<f32>> {
+        let bytes = fs::read(self.disk_path(key)?).ok()?;
+        if bytes.is_empty() || bytes.len() % 4 != 0 {
+            return None;
+        }
+
+        Some(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect(),
+        )
+    }
+
+    fn write_disk(&self, key: &str, vector: &[f32]) -> Result<()> {
+        let Some(path) = self.disk_path(key) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let bytes: Vec<u8> = vector.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+}
+
+/// Collapses whitespace so trivially different copies of a text share a key.
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+struct LruTier {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (Vec<f32>, u64)>,
+    recency: BTreeMap<u64, String>,
+}
+
+impl LruTier {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get(&mut self, key: &str) -> Option<Vec<f32>> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        let (vector, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        *last_used = tick;
+        self.recency.insert(tick, key.to_string());
+
+        Some(vector.clone())
+    }
+
+    fn insert(&mut self, key: String, vector: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (vector, self.tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::models::CacheStats;
+
+/// Turns text into vectors for the stores. `FastEmbedService` is the real
+/// implementation; tests use `HashEmbedder` so they run without a model.
+pub trait Embedder: Send + Sync + 'static {
+    fn embed(&self, text: &str) -> impl Future<Output = Result<Vec<f32>>> + Send;
+
+    fn embed_batch(&self, texts: Vec<&str>) -> impl Future<Output = Result<Vec<Vec<f32>>>> + Send;
+
+    /// Embeds a search query. Models trained with different query and passage
+    /// inputs override this; everything else embeds queries like reviews.
+    fn embed_query(&self, text: &str) -> impl Future<Output = Result<Vec<f32>>> + Send {
+        self.embed(text)
+    }
+
+    fn dimension(&self) -> usize;
+
+    /// Identifies the model in manifests and cache keys.
+    fn model_id(&self) -> &str;
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+}
+
+/// Deterministic embedder for hermetic tests. Each lowercased word is hashed
+/// into a signed bucket, so texts sharing words end up close together.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct HashEmbedder {
+    dimension: usize,
+}
+
+#[cfg(test)]
+impl HashEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+
+    fn embed_sync(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let hash = fnv1a(word.to_lowercase().as_bytes());
+            let bucket = (hash % self.dimension as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[bucket] += sign;
+        }
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        } else {
+            vector[0] = 1.0;
+        }
+
+        vector
+    }
+}
+
+#[cfg(test)]
+impl Embedder for HashEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.embed_sync(text))
+    }
+
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.into_iter().map(|text| self.embed_sync(text)).collect())
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn model_id(&self) -> &str {
+        "test/hash-embedder"
+    }
+}
+
+#[cfg(test)]
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
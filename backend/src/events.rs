@@ -0,0 +1,157 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Buffering of store events for `/events` subscribers.
+#[derive(Debug, Clone, Copy)]
+pub struct EventConfig {
+    /// Recent events kept for clients resuming with `Last-Event-ID`.
+    pub history: usize,
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self { history: 1024 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    ReviewInserted,
+    ReviewDeleted,
+    AlertMatched,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::ReviewInserted => "review.inserted",
+            EventKind::ReviewDeleted => "review.deleted",
+            EventKind::AlertMatched => "alert.matched",
+        }
+    }
+}
+
+/// One write to the store, numbered in publish order. `id` is the SSE id
+/// and `data` the JSON payload sent as the SSE `data` field.
+#[derive(Debug, Clone)]
+pub struct StoreEvent {
+    pub seq: u64,
+    pub id: String,
+    pub kind: EventKind,
+    pub data: String,
+}
+
+struct History {
+    next_seq: u64,
+    events: VecDeque<StoreEvent>,
+}
+
+/// What a new `/events` subscriber gets: the events it missed, then
+/// `receiver` for everything published afterwards.
+pub struct Subscription {
+    /// Set when the client's `Last-Event-ID` can't be resumed from (another
+    /// run of the server, or older than the buffered history). The client
+    /// should reload its state instead of relying on a replay.
+    pub resync: bool,
+    /// Sequence number of the last event published before subscribing.
+    pub last_seq: u64,
+    pub backlog: Vec<StoreEvent>,
+    pub receiver: broadcast::Receiver<StoreEvent>,
+}
+
+/// Fans store writes out to `/events` subscribers. Sequence numbers start at
+/// 1 with every run of the process, so event ids are `<epoch>-<seq>` with a
+/// per-boot epoch; the last `history` events are kept so a reconnecting
+/// client can pick up where it left off.
+pub struct EventBus {
+    epoch: String,
+    sender: broadcast::Sender<StoreEvent>,
+    history: Mutex<History>,
+    capacity: usize,
+}
+
+impl EventBus {
+    pub fn new(config: EventConfig) -> Self {
+        let (sender, _) = broadcast::channel(config.history.max(16));
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self {
+            epoch: format!("{:x}", started.as_nanos()),
+            sender,
+            history: Mutex::new(History {
+                next_seq: 1,
+                events: VecDeque::new(),
+            }),
+            capacity: config.history,
+        }
+    }
+
+    /// The SSE id of the event numbered `seq`.
+    pub fn event_id(&self, seq: u64) -> String {
+        format!("{}-{}", self.epoch, seq)
+    }
+
+    pub fn publish<T: Serialize>(&self, kind: EventKind, payload: &T) {
+        let data = match serde_json::to_string(payload) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!("Failed to serialize {} event: {}", kind.as_str(), e);
+                return;
+            }
+        };
+
+        // Numbering, buffering and sending under one lock keeps `subscribe`
+        // from seeing an event both in the backlog and on the channel.
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let event = StoreEvent {
+            seq: history.next_seq,
+            id: self.event_id(history.next_seq),
+            kind,
+            data,
+        };
+        history.next_seq += 1;
+
+        if self.capacity > 0 {
+            if history.events.len() == self.capacity {
+                history.events.pop_front();
+            }
+            history.events.push_back(event.clone());
+        }
+
+        // No receivers is fine; nobody is listening yet
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes a client that last saw `last_event_id`. Buffered events
+    /// after it are replayed; an id from another epoch, one the bus hasn't
+    /// reached, or one whose successors were already dropped from the history
+    /// asks the client to resync. Without an id only new events are delivered.
+    pub fn subscribe(&self, last_event_id: Option<&str>) -> Subscription {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+        let last_seq = history.next_seq - 1;
+        let oldest = history.events.front().map_or(history.next_seq, |event| event.seq);
+
+        let resume_from = last_event_id.map(|id| {
+            id.trim()
+                .split_once('-')
+                .filter(|(epoch, _)| *epoch == self.epoch)
+                .and_then(|(_, seq)| seq.parse::<u64>().ok())
+                .filter(|&seq| seq <= last_seq && seq + 1 >= oldest)
+        });
+
+        let backlog = match resume_from {
+            Some(Some(last)) => history.events.iter().filter(|event| event.seq > last).cloned().collect(),
+            _ => Vec::new(),
+        };
+
+        Subscription {
+            resync: matches!(resume_from, Some(None)),
+            last_seq,
+            backlog,
+            receiver,
+        }
+    }
+}
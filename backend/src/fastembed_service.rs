@@ -1,60 +1,476 @@
 use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use std::time::Duration;
+use fastembed::{
+    EmbeddingModel, InitOptions, InitOptionsUserDefined, Pooling, TextEmbedding, TokenizerFiles,
+    UserDefinedEmbeddingModel,
+};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::embed_cache::EmbeddingCache;
+use crate::embedder::Embedder;
+use crate::models::CacheStats;
+
+/// Limits for the embedding micro-batcher.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    /// Maximum number of texts sent to the model in a single call.
+    pub max_batch_size: usize,
+    /// How long the worker keeps collecting texts after the first one arrives.
+    pub max_wait: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 32,
+            max_wait: Duration::from_millis(5),
+        }
+    }
+}
+
+/// Prefix used in `--model` to point at a local model directory.
+const LOCAL_MODEL_PREFIX: &str = "local:";
+
+/// Where the embedding model is loaded from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelSource {
+    /// One of fastembed's built-in models. It is read from the cache directory
+    /// and only downloaded when the files are not there yet.
+    Builtin(EmbeddingModel),
+    /// A user-provided ONNX model with its tokenizer files, read from a local
+    /// directory without touching the network.
+    Local { dir: PathBuf, pooling: Pooling },
+}
+
+impl ModelSource {
+    /// Parses a `--model` value: a fastembed model code such as
+    /// `Xenova/bge-small-en-v1.5`, an `EmbeddingModel` variant name such as
+    /// `BGESmallENV15`, or `local:<dir>` for a user-defined model.
+    pub fn from_spec(spec: &str, pooling: Pooling) -> Result<Self> {
+        if let Some(dir) = spec.strip_prefix(LOCAL_MODEL_PREFIX) {
+            return Ok(Self::Local { dir: PathBuf::from(dir), pooling });
+        }
+
+        if let Ok(model) = spec.parse::<EmbeddingModel>() {
+            return Ok(Self::Builtin(model));
+        }
+
+        TextEmbedding::list_supported_models()
+            .into_iter()
+            .find(|info| format!("{:?}", info.model).eq_ignore_ascii_case(spec))
+            .map(|info| Self::Builtin(info.model))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown embedding model '{}' (use {}<dir> for a local ONNX model)",
+                    spec,
+                    LOCAL_MODEL_PREFIX
+                )
+            })
+    }
+
+    /// Input prefixes the model was trained with. Local models are recognized
+    /// as e5 by their directory name; anything else can be set explicitly.
+    pub fn default_prefixes(&self) -> TextPrefixes {
+        let is_e5 = match self {
+            Self::Builtin(model) => matches!(
+                model,
+                EmbeddingModel::MultilingualE5Small
+                    | EmbeddingModel::MultilingualE5Base
+                    | EmbeddingModel::MultilingualE5Large
+            ),
+            Self::Local { dir, .. } => dir
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().contains("e5")),
+        };
+
+        if is_e5 {
+            TextPrefixes::e5()
+        } else {
+            TextPrefixes::default()
+        }
+    }
+
+    /// Stable identifier of the model, in the same form accepted by `from_spec`.
+    pub fn model_id(&self) -> String {
+        match self {
+            Self::Builtin(model) => model.to_string(),
+            Self::Local { dir, .. } => format!("{}{}", LOCAL_MODEL_PREFIX, dir.display()),
+        }
+    }
+}
+
+/// Text put in front of queries and indexed passages before embedding. The
+/// e5 family expects `query: ` and `passage: ` and is noticeably worse at
+/// cross-lingual matching without them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextPrefixes {
+    pub query: String,
+    pub passage: String,
+}
+
+impl TextPrefixes {
+    pub fn e5() -> Self {
+        Self {
+            query: "query: ".to_string(),
+            passage: "passage: ".to_string(),
+        }
+    }
+}
+
+fn prefixed<'a>(prefix: &str, text: &'a str) -> Cow<'a, str> {
+    if prefix.is_empty() {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(format!("{}{}", prefix, text))
+    }
+}
+
+pub fn parse_pooling(value: &str) -> Result<Pooling> {
+    match value.to_ascii_lowercase().as_str() {
+        "mean" => Ok(Pooling::Mean),
+        "cls" => Ok(Pooling::Cls),
+        other => Err(anyhow::anyhow!("Unknown pooling '{}', expected 'mean' or 'cls'", other)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub model: ModelSource,
+    /// Directory holding downloaded built-in models. Falls back to fastembed's
+    /// default (`FASTEMBED_CACHE_DIR` or `.fastembed_cache`) when unset.
+    pub cache_dir: Option<PathBuf>,
+    pub batch: BatchConfig,
+    pub prefixes: TextPrefixes,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            model: ModelSource::Builtin(EmbeddingModel::BGESmallENV15),
+            cache_dir: None,
+            batch: BatchConfig::default(),
+            prefixes: TextPrefixes::default(),
+        }
+    }
+}
+
+struct EmbedJob {
+    text: String,
+    reply: oneshot::Sender<Result<Vec<f32>>>,
+}
+
+/// Handle to the micro-batching worker. Texts from concurrent callers,
+/// whether a search query or the title and chunks of a review, are
+/// coalesced into shared model calls.
+#[derive(Clone)]
+pub struct BatchQueue {
+    jobs: mpsc::UnboundedSender<EmbedJob>,
+}
+
+impl BatchQueue {
+    /// Starts the worker, which runs `embed` on each batch on the blocking
+    /// pool. Must be called from within a Tokio runtime.
+    pub fn start<F>(embed: F, config: BatchConfig) -> Self
+    where
+        F: Fn(Vec<String>) -> Result<Vec<Vec<f32>>> + Send + Sync + 'static,
+    {
+        let (jobs, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run_batcher(Arc::new(embed), receiver, config));
+        Self { jobs }
+    }
+
+    pub async fn embed(&self, text: String) -> Result<Vec<f32>> {
+        self.embed_all(vec![text])
+            .await?
+            .pop()
+            .context("No embedding generated")
+    }
+
+    /// Queues all texts before waiting on any, so they can share a batch
+    /// with each other and with other callers' texts.
+    pub async fn embed_all(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let mut responses = Vec::with_capacity(texts.len());
+        for text in texts {
+            let (reply, response) = oneshot::channel();
+            self.jobs
+                .send(EmbedJob { text, reply })
+                .map_err(|_| anyhow::anyhow!("Embedding worker has stopped"))?;
+            responses.push(response);
+        }
+
+        let mut embeddings = Vec::with_capacity(responses.len());
+        for response in responses {
+            embeddings.push(response.await.context("Embedding worker dropped the request")??);
+        }
+        Ok(embeddings)
+    }
+}
 
 pub struct FastEmbedService {
-    model: Arc<Mutex<TextEmbedding>>,
+    batcher: BatchQueue,
+    model_id: String,
+    dimension: usize,
+    prefixes: TextPrefixes,
+    cache: Option<Arc<EmbeddingCache>>,
 }
 
 //LOL
 impl FastEmbedService {
-    pub fn new() -> Result<Self> {
-        tracing::info!("Initializing FastEmbed service...");
-        
-        let model = TextEmbedding::try_new(
-            InitOptions::new(EmbeddingModel::BGESmallENV15).with_show_download_progress(true),
-        )
-        .context("Failed to initialize FastEmbed model")?;
+    /// Loads the model and starts the batching worker. Must be called from
+    /// within a Tokio runtime.
+    pub fn new(config: EmbeddingConfig) -> Result<Self> {
+        let model_id = config.model.model_id();
+        tracing::info!("Initializing FastEmbed service with model {}...", model_id);
+
+        let (mut model, known_dimension) = match &config.model {
+            ModelSource::Builtin(name) => {
+                let mut options = InitOptions::new(name.clone()).with_show_download_progress(true);
+                if let Some(cache_dir) = &config.cache_dir {
+                    options = options.with_cache_dir(cache_dir.clone());
+                }
+
+                let model = TextEmbedding::try_new(options)
+                    .context("Failed to initialize FastEmbed model")?;
+                let dimension = TextEmbedding::get_model_info(name)?.dim;
+                (model, Some(dimension))
+            }
+            ModelSource::Local { dir, pooling } => {
+                let model = load_local_model(dir, pooling.clone())?;
+                (model, None)
+            }
+        };
+
+        // User-defined models don't advertise their output size, so ask the model
+        let dimension = match known_dimension {
+            Some(dimension) => dimension,
+            None => model
+                .embed(vec!["dimension probe"], None)
+                .context("Failed to run local embedding model")?
+                .first()
+                .map(Vec::len)
+                .context("Local embedding model returned no output")?,
+        };
 
-        tracing::info!("FastEmbed model initialized successfully");
+        tracing::info!("FastEmbed model initialized successfully ({} dimensions)", dimension);
+
+        let model = Mutex::new(model);
+        let batcher = BatchQueue::start(move |texts| run_model(&model, texts), config.batch);
 
         Ok(Self {
-            model: Arc::new(Mutex::new(model)),
+            batcher,
+            model_id,
+            dimension,
+            prefixes: config.prefixes,
+            cache: None,
         })
     }
 
-    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
-        let mut model = self.model.lock().map_err(|e| anyhow::anyhow!("Failed to lock model: {}", e))?;
-        
-        let embeddings = model
-            .embed(vec![text], None)
-            .context("Failed to generate embeddings")?;
+    /// Serves repeated texts from `cache` instead of running the model.
+    pub fn with_cache(mut self, cache: EmbeddingCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Embeds a single text. Cached texts are answered directly, the rest are
+    /// coalesced into one model call by the batching worker.
+    pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        let cache_key = self.cache.as_ref().map(|_| EmbeddingCache::key(&self.model_id, text));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(embedding) = cache.get(key) {
+                return Ok(embedding);
+            }
+        }
+
+        let embedding = self.batcher.embed(text.to_string()).await?;
+
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            cache.put(key, &embedding);
+        }
+
+        Ok(embedding)
+    }
+
+    /// Embeds several texts through the batching worker, so they are merged
+    /// with whatever else is being embedded at the same time.
+    pub async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let Some(cache) = &self.cache else {
+            return self.batcher.embed_all(texts.into_iter().map(str::to_string).collect()).await;
+        };
+
+        // Only the texts the cache doesn't know go through the model
+        let keys: Vec<String> = texts.iter().map(|text| EmbeddingCache::key(&self.model_id, text)).collect();
+        let mut embeddings: Vec<Option<Vec<f32>>> = keys.iter().map(|key| cache.get(key)).collect();
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| embeddings[i].is_none()).collect();
+
+        if !missing.is_empty() {
+            let computed = self
+                .batcher
+                .embed_all(missing.iter().map(|&i| texts[i].to_string()).collect())
+                .await?;
+            for (i, embedding) in missing.into_iter().zip(computed) {
+                cache.put(&keys[i], &embedding);
+                embeddings[i] = Some(embedding);
+            }
+        }
 
         embeddings
             .into_iter()
-            .next()
-            .context("No embedding generated")
+            .map(|embedding| embedding.context("No embedding generated"))
+            .collect()
     }
+}
 
-    pub fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
-        let mut model = self.model.lock().map_err(|e| anyhow::anyhow!("Failed to lock model: {}", e))?;
-        
-        let embeddings = model
-            .embed(texts, None)
-            .context("Failed to generate batch embeddings")?;
+impl Embedder for FastEmbedService {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_text(&prefixed(&self.prefixes.passage, text)).await
+    }
 
-        Ok(embeddings)
+    async fn embed_batch(&self, texts: Vec<&str>) -> Result<Vec<Vec<f32>>> {
+        let texts: Vec<Cow<str>> = texts
+            .into_iter()
+            .map(|text| prefixed(&self.prefixes.passage, text))
+            .collect();
+        FastEmbedService::embed_batch(self, texts.iter().map(AsRef::as_ref).collect()).await
     }
 
-    pub fn get_embedding_dimension() -> usize {
-        384 // BGE-small-en-v1.5 produces 384-dimensional embeddings
+    async fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_text(&prefixed(&self.prefixes.query, text)).await
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
     }
 }
 
 impl Clone for FastEmbedService {
     fn clone(&self) -> Self {
         Self {
-            model: Arc::clone(&self.model),
+            batcher: self.batcher.clone(),
+            model_id: self.model_id.clone(),
+            dimension: self.dimension,
+            prefixes: self.prefixes.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+/// Loads `model.onnx` (or the only `.onnx` file) plus the Hugging Face
+/// tokenizer files from `dir`.
+fn load_local_model(dir: &Path, pooling: Pooling) -> Result<TextEmbedding> {
+    let read = |name: &str| {
+        std::fs::read(dir.join(name))
+            .with_context(|| format!("Failed to read {} from {}", name, dir.display()))
+    };
+
+    let onnx_path = find_onnx_file(dir)?;
+    let onnx_file = std::fs::read(&onnx_path)
+        .with_context(|| format!("Failed to read {}", onnx_path.display()))?;
+
+    let tokenizer_files = TokenizerFiles {
+        tokenizer_file: read("tokenizer.json")?,
+        config_file: read("config.json")?,
+        special_tokens_map_file: read("special_tokens_map.json")?,
+        tokenizer_config_file: read("tokenizer_config.json")?,
+    };
+
+    let model = UserDefinedEmbeddingModel::new(onnx_file, tokenizer_files).with_pooling(pooling);
+    TextEmbedding::try_new_from_user_defined(model, InitOptionsUserDefined::default())
+        .context("Failed to initialize local embedding model")
+}
+
+fn find_onnx_file(dir: &Path) -> Result<PathBuf> {
+    let default_path = dir.join("model.onnx");
+    if default_path.exists() {
+        return Ok(default_path);
+    }
+
+    let mut candidates = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "onnx") {
+            candidates.push(path);
+        }
+    }
+
+    match candidates.len() {
+        1 => Ok(candidates.remove(0)),
+        0 => Err(anyhow::anyhow!("No .onnx model found in {}", dir.display())),
+        _ => Err(anyhow::anyhow!(
+            "Several .onnx files found in {}, name the one to use model.onnx",
+            dir.display()
+        )),
+    }
+}
+
+fn run_model(model: &Mutex<TextEmbedding>, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+    let mut model = model.lock().map_err(|e| anyhow::anyhow!("Failed to lock model: {}", e))?;
+    model.embed(texts, None)
+}
+
+/// Collects queued texts for up to `max_wait` or `max_batch_size` items,
+/// embeds them in one call and sends each result back to its caller.
+async fn run_batcher<F>(embed: Arc<F>, mut jobs: mpsc::UnboundedReceiver<EmbedJob>, config: BatchConfig)
+where
+    F: Fn(Vec<String>) -> Result<Vec<Vec<f32>>> + Send + Sync + 'static,
+{
+    let max_batch_size = config.max_batch_size.max(1);
+
+    while let Some(first) = jobs.recv().await {
+        let deadline = Instant::now() + config.max_wait;
+        let mut batch = vec![first];
+
+        while batch.len() < max_batch_size {
+            match tokio::time::timeout_at(deadline, jobs.recv()).await {
+                Ok(Some(job)) => batch.push(job),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let (texts, replies): (Vec<String>, Vec<_>) =
+            batch.into_iter().map(|job| (job.text, job.reply)).unzip();
+        let batch_size = texts.len();
+        let embed = Arc::clone(&embed);
+
+        let result = tokio::task::spawn_blocking(move || embed(texts))
+            .await
+            .map_err(|e| anyhow::anyhow!("Embedding task panicked: {}", e))
+            .and_then(|result| result);
+
+        match result {
+            Ok(embeddings) if embeddings.len() == batch_size => {
+                tracing::debug!("Embedded batch of {} texts", batch_size);
+                for (reply, embedding) in replies.into_iter().zip(embeddings) {
+                    let _ = reply.send(Ok(embedding));
+                }
+            }
+            Ok(embeddings) => {
+                let message = format!(
+                    "Model returned {} embeddings for {} texts",
+                    embeddings.len(),
+                    batch_size
+                );
+                for reply in replies {
+                    let _ = reply.send(Err(anyhow::anyhow!(message.clone())));
+                }
+            }
+            Err(e) => {
+                let message = format!("Failed to generate embeddings: {:#}", e);
+                for reply in replies {
+                    let _ = reply.send(Err(anyhow::anyhow!(message.clone())));
+                }
+            }
         }
     }
 }
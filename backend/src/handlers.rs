@@ -1,28 +1,70 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    routing::{delete, get, post},
     Router,
 };
+use futures_util::stream::{self, Stream, StreamExt};
+use std::convert::Infallible;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 
 use crate::models::{
-    ApiResponse, InsertReviewRequest, SearchRequest, SearchResponse, ReviewWithScore,
+    Alert, AlertMatch, ApiResponse, BulkInsertResult, BulkInsertStatus, ClusterRequest, ClusterRun, CreateAlertRequest, CreateLabelRequest, Label, LabelBackfillReport, ProductAspects, ExplainResponse, InsertReviewRequest, CreateProductRequest, Product, ProductFields, ProductImportReport, ProductQuery, ProductSummary, ProductSummaryQuery, SimilarProduct, SimilarProductsQuery, DuplicateConflict, DuplicateQuery, PiiRejection, DuplicateReport, ModerationDecision, ModerationDecisionRequest, ModerationItem, ModerationStatus, ReviewFilters, SearchRequest, SearchResponse, StatsResponse,
 };
+use crate::clustering::ClusterScope;
+use crate::duplicates::DuplicateReview;
+use crate::events::Subscription;
+use crate::redaction::PiiRejected;
+use crate::embedder::Embedder;
+use crate::fastembed_service::FastEmbedService;
+use crate::language::normalize_language;
 use crate::spfresh_vector_store::SPFreshVectorStore;
 
-pub type AppState = Arc<Mutex<SPFreshVectorStore>>;
+pub type AppState<E = FastEmbedService> = Arc<RwLock<SPFreshVectorStore<E>>>;
 
-pub fn create_router(store: AppState) -> Router {
+/// Representative reviews per polarity in a product summary.
+const DEFAULT_REPRESENTATIVES: usize = 3;
+
+/// Products returned by `/products/{id}/similar` unless asked otherwise.
+const DEFAULT_SIMILAR_PRODUCTS: usize = 10;
+
+pub fn create_router<E: Embedder>(store: AppState<E>) -> Router {
     Router::new()
         .route("/health", get(health_check))
-        .route("/stats", get(get_stats))
-        .route("/reviews", post(insert_review))
-        .route("/reviews/bulk", post(insert_reviews_bulk))
-        .route("/search", post(search_reviews))
+        .route("/stats", get(get_stats::<E>))
+        .route("/reviews", post(insert_review::<E>))
+        .route("/reviews/bulk", post(insert_reviews_bulk::<E>))
+        .route("/reviews/:id", delete(delete_review::<E>))
+        .route("/search", post(search_reviews::<E>))
+        .route("/search/explain", post(explain_search::<E>))
+        .route("/alerts", post(create_alert::<E>).get(list_alerts::<E>))
+        .route("/alerts/:id", delete(delete_alert::<E>))
+        .route("/alerts/:id/matches", get(alert_matches::<E>))
+        .route("/events", get(stream_events::<E>))
+        .route("/products", post(create_product::<E>).get(list_products::<E>))
+        .route("/products/bulk", post(import_products::<E>))
+        .route(
+            "/products/:id",
+            get(get_product::<E>).put(update_product::<E>).delete(delete_product::<E>),
+        )
+        .route("/products/:id/aspects", get(product_aspects::<E>))
+        .route("/products/:id/summary", get(product_summary::<E>))
+        .route("/products/:id/similar", get(similar_products::<E>))
+        .route("/labels", post(upsert_label::<E>).get(list_labels::<E>))
+        .route("/labels/backfill", post(backfill_labels::<E>))
+        .route("/labels/:name", delete(delete_label::<E>))
+        .route("/analytics/clusters", post(cluster_reviews::<E>).get(latest_clusters::<E>))
+        .route("/analytics/duplicates", get(duplicate_report::<E>))
+        .route("/moderation/queue", get(moderation_queue::<E>))
+        .route("/moderation/:id/approve", post(approve_review::<E>))
+        .route("/moderation/:id/reject", post(reject_review::<E>))
         .with_state(store)
         .layer(CorsLayer::permissive())
 }
@@ -31,73 +73,602 @@ async fn health_check() -> Json<ApiResponse<String>> {
     Json(ApiResponse::success("Service is healthy".to_string()))
 }
 
-async fn get_stats(State(store): State<AppState>) -> Json<ApiResponse<(usize, usize)>> {
-    let store = store.lock().await;
+async fn get_stats<E: Embedder>(State(store): State<AppState<E>>) -> Json<ApiResponse<StatsResponse>> {
+    let store = store.read().await;
     let stats = store.get_stats().await;
     Json(ApiResponse::success(stats))
 }
 
-async fn insert_review(
-    State(store): State<AppState>,
+async fn insert_review<E: Embedder>(
+    State(store): State<AppState<E>>,
     Json(request): Json<InsertReviewRequest>,
-) -> Result<Json<ApiResponse<crate::models::Review>>, StatusCode> {
-    let store = store.lock().await;
+) -> Result<Json<ApiResponse<crate::models::Review>>, Response> {
+    let store = store.read().await;
     
     match store.insert_review(request).await {
         Ok(review) => Ok(Json(ApiResponse::success(review))),
         Err(e) => {
+            if let Some(duplicate) = e.downcast_ref::<DuplicateReview>() {
+                return Err(duplicate_conflict(duplicate));
+            }
+            if let Some(rejected) = e.downcast_ref::<PiiRejected>() {
+                return Err(pii_rejection(rejected));
+            }
             tracing::error!("Failed to insert review: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
         }
     }
 }
 
-async fn insert_reviews_bulk(
-    State(store): State<AppState>,
+/// 422 naming the detectors that found personal data in a rejected review.
+fn pii_rejection(rejected: &PiiRejected) -> Response {
+    let body = ApiResponse {
+        success: false,
+        data: Some(PiiRejection {
+            detectors: rejected.detectors.clone(),
+        }),
+        error: Some(rejected.to_string()),
+    };
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+}
+
+/// 409 naming the stored review a rejected one duplicates.
+fn duplicate_conflict(duplicate: &DuplicateReview) -> Response {
+    let body = ApiResponse {
+        success: false,
+        data: Some(DuplicateConflict {
+            duplicate_of: duplicate.duplicate_of.clone(),
+            similarity: duplicate.similarity,
+        }),
+        error: Some(duplicate.to_string()),
+    };
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
+
+/// Inserts each review like `POST /reviews` and reports what happened to
+/// each one. Rejected duplicates and reviews with personal data are left out
+/// and the rest still go in.
+async fn insert_reviews_bulk<E: Embedder>(
+    State(store): State<AppState<E>>,
     Json(requests): Json<Vec<InsertReviewRequest>>,
-) -> Result<Json<ApiResponse<Vec<crate::models::Review>>>, StatusCode> {
-    let store = store.lock().await;
-    let mut inserted_reviews = Vec::new();
+) -> Result<Json<ApiResponse<Vec<BulkInsertResult>>>, StatusCode> {
+    let store = store.read().await;
+    let mut results = Vec::new();
 
     for request in requests {
+        let mut result = BulkInsertResult {
+            status: BulkInsertStatus::Inserted,
+            review: None,
+            duplicate_of: None,
+            similarity: None,
+            detectors: None,
+            error: None,
+        };
         match store.insert_review(request).await {
-            Ok(review) => inserted_reviews.push(review),
+            Ok(review) => {
+                if review.status == Some(ModerationStatus::Pending) {
+                    result.status = BulkInsertStatus::Pending;
+                }
+                result.review = Some(review);
+            }
             Err(e) => {
-                tracing::error!("Failed to insert review in bulk: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                if let Some(duplicate) = e.downcast_ref::<DuplicateReview>() {
+                    result.status = BulkInsertStatus::Duplicate;
+                    result.duplicate_of = Some(duplicate.duplicate_of.clone());
+                    result.similarity = Some(duplicate.similarity);
+                } else if let Some(rejected) = e.downcast_ref::<PiiRejected>() {
+                    result.status = BulkInsertStatus::Rejected;
+                    result.detectors = Some(rejected.detectors.clone());
+                } else {
+                    tracing::error!("Failed to insert review in bulk: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+                result.error = Some(e.to_string());
+            }
+        }
+        results.push(result);
+    }
+
+    Ok(Json(ApiResponse::success(results)))
+}
+
+async fn moderation_queue<E: Embedder>(State(store): State<AppState<E>>) -> Json<ApiResponse<Vec<ModerationItem>>> {
+    let store = store.read().await;
+    Json(ApiResponse::success(store.moderation_queue().await))
+}
+
+async fn approve_review<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(review_id): Path<String>,
+    Json(request): Json<ModerationDecisionRequest>,
+) -> Result<Json<ApiResponse<ModerationDecision>>, Response> {
+    moderate(store, review_id, ModerationStatus::Approved, request).await
+}
+
+async fn reject_review<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(review_id): Path<String>,
+    Json(request): Json<ModerationDecisionRequest>,
+) -> Result<Json<ApiResponse<ModerationDecision>>, Response> {
+    moderate(store, review_id, ModerationStatus::Rejected, request).await
+}
+
+async fn moderate<E: Embedder>(
+    store: AppState<E>,
+    review_id: String,
+    status: ModerationStatus,
+    request: ModerationDecisionRequest,
+) -> Result<Json<ApiResponse<ModerationDecision>>, Response> {
+    if request.moderator.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+
+    let store = store.read().await;
+
+    match store.moderate(&review_id, status, request).await {
+        Ok(Some(decision)) => Ok(Json(ApiResponse::success(decision))),
+        Ok(None) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => match e.downcast_ref::<DuplicateReview>() {
+            Some(duplicate) => Err(duplicate_conflict(duplicate)),
+            None => {
+                tracing::error!("Failed to moderate review {}: {}", review_id, e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
             }
+        },
+    }
+}
+
+async fn delete_review<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(review_id): Path<String>,
+) -> Result<Json<ApiResponse<crate::models::Review>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.delete_review(&review_id).await {
+        Ok(Some(review)) => Ok(Json(ApiResponse::success(review))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete review {}: {}", review_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn validate_product(id: &str, fields: &ProductFields) -> Result<(), StatusCode> {
+    if id.trim().is_empty() || fields.name.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+async fn create_product<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(request): Json<CreateProductRequest>,
+) -> Result<Json<ApiResponse<Product>>, StatusCode> {
+    validate_product(&request.id, &request.fields)?;
+
+    let store = store.read().await;
+
+    match store.create_product(request).await {
+        Ok(Some(product)) => Ok(Json(ApiResponse::success(product))),
+        Ok(None) => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to create product: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn import_products<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(requests): Json<Vec<CreateProductRequest>>,
+) -> Result<Json<ApiResponse<ProductImportReport>>, StatusCode> {
+    for request in &requests {
+        validate_product(&request.id, &request.fields)?;
+    }
+
+    let store = store.read().await;
+
+    match store.import_products(requests).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => {
+            tracing::error!("Failed to import products: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn list_products<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Query(query): Query<ProductQuery>,
+) -> Json<ApiResponse<Vec<Product>>> {
+    let store = store.read().await;
+    let products = store.list_products(query.category.as_deref(), query.brand.as_deref()).await;
+    Json(ApiResponse::success(products))
+}
+
+async fn get_product<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+) -> Result<Json<ApiResponse<Product>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.get_product(&product_id).await {
+        Some(product) => Ok(Json(ApiResponse::success(product))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn update_product<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+    Json(fields): Json<ProductFields>,
+) -> Result<Json<ApiResponse<Product>>, StatusCode> {
+    validate_product(&product_id, &fields)?;
+
+    let store = store.read().await;
+
+    match store.update_product(&product_id, fields).await {
+        Ok(Some(product)) => Ok(Json(ApiResponse::success(product))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to update product {}: {}", product_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn delete_product<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+) -> Result<Json<ApiResponse<Product>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.delete_product(&product_id).await {
+        Ok(Some(product)) => Ok(Json(ApiResponse::success(product))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete product {}: {}", product_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn product_aspects<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+) -> Result<Json<ApiResponse<ProductAspects>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.product_aspects(&product_id).await {
+        Ok(Some(aspects)) => Ok(Json(ApiResponse::success(aspects))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to extract aspects of product {}: {}", product_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
+}
+
+async fn product_summary<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+    Query(query): Query<ProductSummaryQuery>,
+) -> Result<Json<ApiResponse<ProductSummary>>, StatusCode> {
+    let representatives = query.representatives.unwrap_or(DEFAULT_REPRESENTATIVES);
+    if representatives == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let store = store.read().await;
+
+    match store.product_summary(&product_id, representatives).await {
+        Ok(Some(summary)) => Ok(Json(ApiResponse::success(summary))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to summarize product {}: {}", product_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn similar_products<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(product_id): Path<String>,
+    Query(query): Query<SimilarProductsQuery>,
+) -> Result<Json<ApiResponse<Vec<SimilarProduct>>>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_SIMILAR_PRODUCTS);
+    if limit == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    Ok(Json(ApiResponse::success(inserted_reviews)))
+    let store = store.read().await;
+
+    match store.similar_products(&product_id, limit).await {
+        Ok(Some(similar)) => Ok(Json(ApiResponse::success(similar))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to find products similar to {}: {}", product_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
-async fn search_reviews(
-    State(store): State<AppState>,
+async fn upsert_label<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(request): Json<CreateLabelRequest>,
+) -> Result<Json<ApiResponse<Label>>, StatusCode> {
+    if request.name.trim().is_empty()
+        || request.prototypes.is_empty()
+        || request.prototypes.iter().any(|prototype| prototype.trim().is_empty())
+        || request.threshold.is_some_and(|threshold| !(-1.0..=1.0).contains(&threshold))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let store = store.read().await;
+
+    match store.upsert_label(request).await {
+        Ok(label) => Ok(Json(ApiResponse::success(label))),
+        Err(e) => {
+            tracing::error!("Failed to save label: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn list_labels<E: Embedder>(State(store): State<AppState<E>>) -> Json<ApiResponse<Vec<Label>>> {
+    let store = store.read().await;
+    Json(ApiResponse::success(store.list_labels().await))
+}
+
+async fn delete_label<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<Label>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.delete_label(&name).await {
+        Ok(Some(label)) => Ok(Json(ApiResponse::success(label))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete label {}: {}", name, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn backfill_labels<E: Embedder>(
+    State(store): State<AppState<E>>,
+) -> Result<Json<ApiResponse<LabelBackfillReport>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.backfill_labels().await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => {
+            tracing::error!("Failed to backfill labels: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn cluster_reviews<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(request): Json<ClusterRequest>,
+) -> Result<Json<ApiResponse<ClusterRun>>, StatusCode> {
+    if request.k == Some(0) || request.max_iterations == Some(0) || ClusterScope::new(&request).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let store = store.read().await;
+
+    match store.cluster_reviews(request).await {
+        Ok(run) => Ok(Json(ApiResponse::success(run))),
+        Err(e) => {
+            tracing::error!("Failed to cluster reviews: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn duplicate_report<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Query(query): Query<DuplicateQuery>,
+) -> Result<Json<ApiResponse<DuplicateReport>>, StatusCode> {
+    if query.threshold.is_some_and(|threshold| !(-1.0..=1.0).contains(&threshold)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let store = store.read().await;
+
+    match store.duplicate_report(query.threshold).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => {
+            tracing::error!("Failed to report duplicates: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn latest_clusters<E: Embedder>(
+    State(store): State<AppState<E>>,
+) -> Result<Json<ApiResponse<ClusterRun>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.latest_clusters().await {
+        Some(run) => Ok(Json(ApiResponse::success(run))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Server-sent events for store writes. Each event's id is the server's
+/// epoch and its sequence number, so a reconnecting client's `Last-Event-ID`
+/// replays whatever it missed that is still buffered, and a client that
+/// can't be caught up that way is sent a `resync` event first.
+async fn stream_events<E: Embedder>(
+    State(store): State<AppState<E>>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers.get("last-event-id").and_then(|value| value.to_str().ok());
+    let (subscription, resync_id) = {
+        let store = store.read().await;
+        let subscription = store.subscribe(last_event_id);
+        let resync_id = store.event_id(subscription.last_seq);
+        (subscription, resync_id)
+    };
+    let Subscription { resync, backlog, receiver, .. } = subscription;
+
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event subscriber fell behind, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    // The resync event carries the current position, so reconnecting after
+    // it resumes instead of asking again
+    let resync = resync.then(|| Event::default().id(resync_id).event("resync").data("{}"));
+    let events = stream::iter(resync)
+        .chain(stream::iter(backlog).chain(live).map(|event| {
+            Event::default().id(event.id).event(event.kind.as_str()).data(event.data)
+        }))
+        .map(Ok);
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+fn validate_search(request: &SearchRequest) -> Result<(), StatusCode> {
+    if request.diversity.is_some_and(|diversity| !(0.0..=1.0).contains(&diversity)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let half_life = request.ranking.as_ref().and_then(|ranking| ranking.recency_half_life_days);
+    if half_life.is_some_and(|days| days <= 0.0) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    validate_filters(&request.filters)
+}
+
+fn validate_filters(filters: &ReviewFilters) -> Result<(), StatusCode> {
+    if let Some(languages) = &filters.languages {
+        if languages.iter().any(|language| normalize_language(language).is_none()) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(range) = &filters.sentiment {
+        let bounds = [range.min, range.max];
+        if bounds.iter().flatten().any(|bound| !(-1.0..=1.0).contains(bound)) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn search_reviews<E: Embedder>(
+    State(store): State<AppState<E>>,
     Json(request): Json<SearchRequest>,
 ) -> Result<Json<ApiResponse<SearchResponse>>, StatusCode> {
-    let store = store.lock().await;
-
-    match store.search(&request.query, request.limit.unwrap_or(10)).await {
-        Ok(results) => {
-            let total_found = results.len();
-            let reviews = results.into_iter()
-                .map(|(review, score)| ReviewWithScore {
-                    review,
-                    similarity_score: score,
-                })
-                .collect();
-            
-            let response = SearchResponse {
-                reviews,
-                total_found,
-            };
-            
-            Ok(Json(ApiResponse::success(response)))
-        }
+    validate_search(&request)?;
+
+    let store = store.read().await;
+
+    match store.search_response(&request).await {
+        Ok(response) => Ok(Json(ApiResponse::success(response))),
         Err(e) => {
             tracing::error!("Failed to search reviews: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
+
+async fn explain_search<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(request): Json<SearchRequest>,
+) -> Result<Json<ApiResponse<ExplainResponse>>, StatusCode> {
+    validate_search(&request)?;
+
+    let store = store.read().await;
+
+    match store.explain(&request).await {
+        Ok(explanation) => Ok(Json(ApiResponse::success(explanation))),
+        Err(e) => {
+            tracing::error!("Failed to explain search: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn create_alert<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Json(request): Json<CreateAlertRequest>,
+) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    validate_filters(&request.filters)?;
+    if !(-1.0..=1.0).contains(&request.threshold) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(url) = &request.webhook_url {
+        if reqwest::Url::parse(url).is_err() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let store = store.read().await;
+
+    match store.create_alert(request).await {
+        Ok(alert) => Ok(Json(ApiResponse::success(alert))),
+        Err(e) => {
+            tracing::error!("Failed to create alert: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn list_alerts<E: Embedder>(State(store): State<AppState<E>>) -> Json<ApiResponse<Vec<Alert>>> {
+    let store = store.read().await;
+    Json(ApiResponse::success(store.list_alerts().await))
+}
+
+async fn delete_alert<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(alert_id): Path<String>,
+) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.delete_alert(&alert_id).await {
+        Ok(Some(alert)) => Ok(Json(ApiResponse::success(alert))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete alert {}: {}", alert_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn alert_matches<E: Embedder>(
+    State(store): State<AppState<E>>,
+    Path(alert_id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<AlertMatch>>>, StatusCode> {
+    let store = store.read().await;
+
+    match store.alert_matches(&alert_id).await {
+        Ok(Some(matches)) => Ok(Json(ApiResponse::success(matches))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to read matches of alert {}: {}", alert_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::models::Label;
+use crate::vector_store::cosine_similarity;
+
+const LABELS_FILE: &str = "labels.json";
+
+/// A label with its prototype embeddings, tied to `model` like saved alerts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLabel {
+    #[serde(flatten)]
+    pub label: Label,
+    pub model: String,
+    pub embeddings: Vec<Vec<f32>>,
+}
+
+impl SavedLabel {
+    /// Whether any of a review's vectors is at least `threshold` similar to
+    /// any prototype.
+    pub fn matches<'a>(&self, vectors: impl IntoIterator<Item = &'a [f32]>) -> bool {
+        vectors.into_iter().any(|vector| {
+            self.embeddings
+                .iter()
+                .any(|prototype| cosine_similarity(vector, prototype) >= self.label.threshold)
+        })
+    }
+}
+
+/// Names of the labels a review's vectors match, in label order.
+pub fn assign_labels(labels: &[SavedLabel], vectors: &[&[f32]]) -> Vec<String> {
+    labels
+        .iter()
+        .filter(|saved| saved.matches(vectors.iter().copied()))
+        .map(|saved| saved.label.name.clone())
+        .collect()
+}
+
+/// Zero-shot label definitions in `data_dir/labels/labels.json`.
+pub struct LabelStore {
+    dir: PathBuf,
+    labels: RwLock<Vec<SavedLabel>>,
+}
+
+impl LabelStore {
+    pub fn load(data_dir: &str) -> Result<Self> {
+        let dir = Path::new(data_dir).join("labels");
+        let path = dir.join(LABELS_FILE);
+
+        let labels = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            dir,
+            labels: RwLock::new(labels),
+        })
+    }
+
+    pub async fn list(&self) -> Vec<Label> {
+        self.labels.read().await.iter().map(|saved| saved.label.clone()).collect()
+    }
+
+    pub async fn saved(&self) -> Vec<SavedLabel> {
+        self.labels.read().await.clone()
+    }
+
+    /// Adds a label, replacing one with the same name.
+    pub async fn upsert(&self, saved: SavedLabel) -> Result<()> {
+        let mut labels = self.labels.write().await;
+        match labels.iter_mut().find(|existing| existing.label.name == saved.label.name) {
+            Some(existing) => *existing = saved,
+            None => labels.push(saved),
+        }
+        self.save(&labels)
+    }
+
+    pub async fn remove(&self, name: &str) -> Result<Option<Label>> {
+        let mut labels = self.labels.write().await;
+        let Some(position) = labels.iter().position(|saved| saved.label.name == name) else {
+            return Ok(None);
+        };
+
+        let removed = labels.remove(position);
+        self.save(&labels)?;
+        Ok(Some(removed.label))
+    }
+
+    /// Swaps in prototype embeddings computed with `model`, keyed by name.
+    pub async fn replace_embeddings(&self, model: &str, embeddings: HashMap<String, Vec<Vec<f32>>>) -> Result<()> {
+        if embeddings.is_empty() {
+            return Ok(());
+        }
+
+        let mut labels = self.labels.write().await;
+        for saved in labels.iter_mut() {
+            if let Some(embedding) = embeddings.get(&saved.label.name) {
+                saved.model = model.to_string();
+                saved.embeddings = embedding.clone();
+            }
+        }
+        self.save(&labels)
+    }
+
+    fn save(&self, labels: &[SavedLabel]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let path = self.dir.join(LABELS_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(labels)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+}
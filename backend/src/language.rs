@@ -0,0 +1,32 @@
+use whatlang::Lang;
+
+/// Detections below this confidence are dropped unless whatlang itself calls
+/// them reliable. Short reviews rarely reach `is_reliable`, but a middling
+/// confidence on them is still usually right.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Detects the language of `text` as an ISO 639-3 code such as `eng` or
+/// `tha`. Returns `None` when the text is too short or ambiguous to tell.
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() && info.confidence() < MIN_CONFIDENCE {
+        return None;
+    }
+
+    Some(info.lang().code().to_string())
+}
+
+/// Turns a `languages` filter value into the ISO 639-3 code stored on
+/// reviews. Accepts codes (`tha`) and English names (`Thai`), in any case.
+pub fn normalize_language(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+
+    Lang::from_code(value.as_str())
+        .or_else(|| {
+            Lang::all()
+                .iter()
+                .copied()
+                .find(|lang| lang.eng_name().eq_ignore_ascii_case(&value))
+        })
+        .map(|lang| lang.code().to_string())
+}
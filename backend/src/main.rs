@@ -1,6 +1,24 @@
+mod catalog;
+mod chunking;
+mod clustering;
+mod alerts;
+mod aspects;
+mod duplicates;
+mod embed_cache;
+mod embedder;
+mod events;
 mod fastembed_service;
 mod handlers;
+mod labels;
+mod language;
+mod manifest;
 mod models;
+mod moderation;
+mod product_index;
+mod ranking;
+mod redaction;
+mod search;
+mod sentiment;
 mod spfresh_bindings;
 mod spfresh_vector_store;
 mod vector_store;
@@ -9,13 +27,26 @@ mod vector_store;
 mod tests;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::RwLock;
 
-use fastembed_service::FastEmbedService;
+use alerts::DeliveryConfig;
+use aspects::{parse_aspects, AspectConfig};
+use chunking::ChunkConfig;
+use duplicates::{parse_duplicate_mode, DuplicateConfig};
+use embed_cache::EmbeddingCache;
+use embedder::Embedder;
+use events::EventConfig;
+use fastembed_service::{parse_pooling, BatchConfig, EmbeddingConfig, FastEmbedService, ModelSource};
 use handlers::{create_router, AppState};
-use spfresh_vector_store::SPFreshVectorStore;
+use manifest::IndexManifest;
+use ranking::RankingConfig;
+use redaction::{parse_detector, parse_redaction_mode, RedactionConfig};
+use sentiment::SentimentAnalyzer;
+use spfresh_vector_store::{SPFreshVectorStore, StoreConfig};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -25,6 +56,211 @@ struct Args {
 
     #[arg(short, long, default_value = "./data")]
     data_dir: String,
+
+    /// Embedding model: a fastembed model name or code, or `local:<dir>` for an ONNX model
+    #[arg(long, default_value = "BGESmallENV15")]
+    model: String,
+
+    /// Pooling applied to local models (`mean` or `cls`)
+    #[arg(long, default_value = "mean")]
+    model_pooling: String,
+
+    /// Directory where built-in models are cached; pre-populate it for offline use
+    #[arg(long)]
+    model_cache_dir: Option<PathBuf>,
+
+    /// Text put in front of search queries (defaults to `query: ` for e5 models)
+    #[arg(long)]
+    query_prefix: Option<String>,
+
+    /// Text put in front of indexed review text (defaults to `passage: ` for e5 models)
+    #[arg(long)]
+    passage_prefix: Option<String>,
+
+    /// Maximum number of texts embedded in one model call
+    #[arg(long, default_value = "32")]
+    embed_batch_size: usize,
+
+    /// How long to wait for more texts before running a partial batch
+    #[arg(long, default_value = "5")]
+    embed_batch_wait_ms: u64,
+
+    /// Number of embeddings kept in the in-memory cache tier (0 disables it)
+    #[arg(long, default_value = "10000")]
+    embed_cache_size: usize,
+
+    /// Disable the on-disk embedding cache in `<data-dir>/embed_cache`
+    #[arg(long)]
+    no_disk_embed_cache: bool,
+
+    /// Sentences per review chunk; each chunk is embedded as its own vector
+    #[arg(long, default_value = "4")]
+    chunk_sentences: usize,
+
+    /// Sentences shared between consecutive chunks
+    #[arg(long, default_value = "1")]
+    chunk_overlap: usize,
+
+    /// Weight of the semantic similarity in the final ranking score
+    #[arg(long, default_value = "1.0")]
+    semantic_weight: f32,
+
+    /// Weight of the exponential time-decay boost (0 disables it)
+    #[arg(long, default_value = "0.0")]
+    recency_weight: f32,
+
+    /// Review age in days at which the recency boost halves
+    #[arg(long, default_value = "180")]
+    recency_half_life_days: f32,
+
+    /// Weight of the rating boost; negative values favour low ratings
+    #[arg(long, default_value = "0.0", allow_hyphen_values = true)]
+    rating_weight: f32,
+
+    /// Attempts per alert webhook delivery, including the first
+    #[arg(long, default_value = "4")]
+    webhook_attempts: u32,
+
+    /// Delay before the first webhook retry, doubled after each failure
+    #[arg(long, default_value = "500")]
+    webhook_backoff_ms: u64,
+
+    /// Recent events kept for `/events` clients resuming with Last-Event-ID
+    #[arg(long, default_value = "1024")]
+    event_history: usize,
+
+    /// JSON sentiment lexicon with `positive`, `negative` and `negations` word
+    /// lists; lists left out keep the built-in ones
+    #[arg(long)]
+    sentiment_lexicon: Option<PathBuf>,
+
+    /// Comma-separated aspects for `/products/{id}/aspects`, each a name or
+    /// `name=description`; the description is what gets embedded
+    #[arg(long)]
+    aspects: Option<String>,
+
+    /// Minimum similarity between a review clause and an aspect
+    #[arg(long, default_value = "0.6")]
+    aspect_threshold: f32,
+
+    /// What to do with a review nearly duplicating a stored one: `off`,
+    /// `flag` (store it with `duplicate_of`) or `reject` (409)
+    #[arg(long, default_value = "off")]
+    duplicates: String,
+
+    /// Minimum similarity between two reviews' mean vectors for one to be a
+    /// duplicate of the other
+    #[arg(long, default_value = "0.95")]
+    duplicate_threshold: f32,
+
+    /// Queue new reviews as pending until a moderator approves them
+    #[arg(long)]
+    moderation: bool,
+
+    /// What to do with personal data (emails, phone and card numbers, Thai
+    /// national IDs) in new reviews: `off`, `mask`, `hash` or `reject`
+    #[arg(long, default_value = "off")]
+    redact: String,
+
+    /// Extra personal data pattern as `name=regex`; repeat for several
+    #[arg(long)]
+    redact_pattern: Vec<String>,
+
+    /// Secret keying the hashes of `--redact hash`; required in that mode
+    #[arg(long, env = "REDACT_SECRET", hide_env_values = true)]
+    redact_secret: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve with the model recorded in the data directory while re-embedding
+    /// every review with another model, then switch over to it
+    Reindex {
+        /// Model to migrate to, in the same form as the top-level `--model`
+        #[arg(long)]
+        model: String,
+    },
+}
+
+impl Args {
+    fn embedding_config(&self, model_spec: &str) -> Result<EmbeddingConfig> {
+        let model = ModelSource::from_spec(model_spec, parse_pooling(&self.model_pooling)?)?;
+
+        let mut prefixes = model.default_prefixes();
+        if let Some(query_prefix) = &self.query_prefix {
+            prefixes.query = query_prefix.clone();
+        }
+        if let Some(passage_prefix) = &self.passage_prefix {
+            prefixes.passage = passage_prefix.clone();
+        }
+
+        Ok(EmbeddingConfig {
+            model,
+            cache_dir: self.model_cache_dir.clone(),
+            batch: BatchConfig {
+                max_batch_size: self.embed_batch_size,
+                max_wait: Duration::from_millis(self.embed_batch_wait_ms),
+            },
+            prefixes,
+        })
+    }
+
+    fn embedding_cache(&self) -> EmbeddingCache {
+        let disk_dir = (!self.no_disk_embed_cache).then(|| PathBuf::from(&self.data_dir).join("embed_cache"));
+        EmbeddingCache::new(self.embed_cache_size, disk_dir)
+    }
+
+    fn store_config(&self) -> Result<StoreConfig> {
+        anyhow::ensure!(self.recency_half_life_days > 0.0, "--recency-half-life-days must be positive");
+
+        Ok(StoreConfig {
+            chunking: ChunkConfig {
+                window_sentences: self.chunk_sentences,
+                overlap_sentences: self.chunk_overlap,
+            },
+            ranking: RankingConfig {
+                semantic_weight: self.semantic_weight,
+                recency_weight: self.recency_weight,
+                recency_half_life_days: self.recency_half_life_days,
+                rating_weight: self.rating_weight,
+            },
+            webhooks: DeliveryConfig {
+                max_attempts: self.webhook_attempts,
+                initial_backoff: Duration::from_millis(self.webhook_backoff_ms),
+                ..DeliveryConfig::default()
+            },
+            events: EventConfig {
+                history: self.event_history,
+            },
+            sentiment: match &self.sentiment_lexicon {
+                Some(path) => SentimentAnalyzer::load(path)?,
+                None => SentimentAnalyzer::default(),
+            },
+            aspects: AspectConfig {
+                aspects: match &self.aspects {
+                    Some(spec) => parse_aspects(spec)?,
+                    None => AspectConfig::default().aspects,
+                },
+                threshold: self.aspect_threshold,
+            },
+            duplicates: DuplicateConfig {
+                mode: parse_duplicate_mode(&self.duplicates)?,
+                threshold: self.duplicate_threshold,
+            },
+            moderation: self.moderation,
+            redaction: RedactionConfig::with_custom(
+                parse_redaction_mode(&self.redact)?,
+                self.redact_pattern
+                    .iter()
+                    .map(|spec| parse_detector(spec))
+                    .collect::<Result<_>>()?,
+                self.redact_secret.as_deref(),
+            )?,
+        })
+    }
 }
 
 #[tokio::main]
@@ -39,12 +275,47 @@ async fn main() -> Result<()> {
     tracing::info!("Starting Review Search Backend on port {}", args.port);
     tracing::info!("Data directory: {}", args.data_dir);
 
+    // While reindexing, keep serving with the model that built the data directory
+    let manifest = IndexManifest::load(&args.data_dir)?;
+    let serving_model = match (&args.command, &manifest) {
+        (Some(Command::Reindex { .. }), Some(manifest)) => manifest.model.clone(),
+        _ => args.model.clone(),
+    };
+
     // Initialize FastEmbed service
-    let fastembed_service = FastEmbedService::new()?;
+    let fastembed_service = FastEmbedService::new(args.embedding_config(&serving_model)?)?
+        .with_cache(args.embedding_cache());
+
+    let current = IndexManifest::new(fastembed_service.model_id(), fastembed_service.dimension());
+    if let Some(manifest) = &manifest {
+        manifest.check_compatible(&current)?;
+        if manifest.index_format_version != current.index_format_version {
+            tracing::info!(
+                "Index format changed from v{} to v{}, rebuilding from reviews.jsonl",
+                manifest.index_format_version,
+                current.index_format_version
+            );
+        }
+    }
 
     // Initialize SPFresh vector store with FastEmbed service
-    let vector_store = SPFreshVectorStore::new(&args.data_dir, fastembed_service).await?;
-    let app_state: AppState = Arc::new(Mutex::new(vector_store));
+    let vector_store = SPFreshVectorStore::new(&args.data_dir, fastembed_service, args.store_config()?).await?;
+    if manifest.as_ref() != Some(&current) {
+        current.save(&args.data_dir)?;
+    }
+    let app_state: AppState = Arc::new(RwLock::new(vector_store));
+
+    if let Some(Command::Reindex { model }) = &args.command {
+        let config = args.embedding_config(model)?;
+        let cache = args.embedding_cache();
+        let state = Arc::clone(&app_state);
+        let data_dir = args.data_dir.clone();
+        tokio::spawn(async move {
+            if let Err(e) = reindex(state, &data_dir, config, cache).await {
+                tracing::error!("Reindex failed, still serving the previous model: {:#}", e);
+            }
+        });
+    }
 
     // Create router
     let app = create_router(app_state);
@@ -58,3 +329,31 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Re-embeds every review with the model from `config` next to the serving
+/// index, then swaps the new store in and records the model in the manifest.
+async fn reindex(
+    state: AppState,
+    data_dir: &str,
+    config: EmbeddingConfig,
+    cache: EmbeddingCache,
+) -> Result<()> {
+    let target = config.model.model_id();
+    tracing::info!("Reindexing {} with model {}", data_dir, target);
+
+    let fastembed_service = tokio::task::spawn_blocking(move || FastEmbedService::new(config))
+        .await??
+        .with_cache(cache);
+    let mut rebuilt = state.read().await.rebuild_with(fastembed_service).await?;
+
+    // Writers are blocked from here on, so nothing is missed between the
+    // catch-up and the swap
+    let mut store = state.write().await;
+    rebuilt.catch_up(&store).await?;
+    rebuilt.promote(&store).await?;
+    IndexManifest::new(rebuilt.model_id(), rebuilt.dimension()).save(data_dir)?;
+    *store = rebuilt;
+
+    tracing::info!("Reindex complete, now serving model {}", target);
+    Ok(())
+}
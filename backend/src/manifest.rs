@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Bumped whenever the way reviews are turned into index vectors changes.
+pub const INDEX_FORMAT_VERSION: u32 = 3;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Records which embedding model produced the vectors in a data directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexManifest {
+    pub model: String,
+    pub dimension: usize,
+    /// Whether stored vectors are L2-normalized. fastembed always normalizes
+    /// its text embeddings.
+    pub normalized: bool,
+    pub index_format_version: u32,
+}
+
+impl IndexManifest {
+    pub fn new(model: &str, dimension: usize) -> Self {
+        Self {
+            model: model.to_string(),
+            dimension,
+            normalized: true,
+            index_format_version: INDEX_FORMAT_VERSION,
+        }
+    }
+
+    pub fn load(data_dir: &str) -> Result<Option<Self>> {
+        let path = Path::new(data_dir).join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        Ok(Some(manifest))
+    }
+
+    /// Writes the manifest through a temporary file so readers never see a
+    /// half-written one.
+    pub fn save(&self, data_dir: &str) -> Result<()> {
+        fs::create_dir_all(data_dir)?;
+
+        let path = Path::new(data_dir).join(MANIFEST_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+
+    /// Fails when vectors built by `self` can't be mixed with vectors from
+    /// `current`. A format version change alone is fine because the index is
+    /// rebuilt from `reviews.jsonl` on startup.
+    pub fn check_compatible(&self, current: &IndexManifest) -> Result<()> {
+        if self.model != current.model || self.dimension != current.dimension {
+            return Err(anyhow::anyhow!(
+                "Data directory was indexed with model {} ({} dimensions) but the configured model is {} ({} dimensions). \
+                 Run with `reindex --model {}` to migrate the existing reviews.",
+                self.model,
+                self.dimension,
+                current.model,
+                current.dimension,
+                current.model
+            ));
+        }
+
+        Ok(())
+    }
+}
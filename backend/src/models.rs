@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Review {
@@ -8,6 +9,115 @@ pub struct Review {
     pub product_id: String,
     pub review_rating: u8,
     pub timestamp: String,
+    /// ISO 639-3 code detected from the title and body, e.g. `eng` or `tha`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Lexicon sentiment of the title and body in [-1, 1].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<f32>,
+    /// Zero-shot labels whose prototypes the review matched.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Stored review this one nearly duplicates, when duplicate flagging is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    /// Detectors that found personal data in the submitted title or body,
+    /// which was redacted before the review was embedded and stored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<String>,
+    /// Moderation state of reviews submitted in moderation mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ModerationStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    /// Stored in the moderation queue, not searchable yet.
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Automatic hints shown to moderators with a queued review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationFlag {
+    /// Very little text in the title and body.
+    ShortText,
+    /// Written entirely in capital letters.
+    AllCaps,
+    /// Contains a link.
+    Link,
+    /// The body is exactly that of a stored or queued review.
+    DuplicateBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationItem {
+    pub review: Review,
+    pub flags: Vec<ModerationFlag>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationDecisionRequest {
+    pub moderator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A moderator's decision on a queued review, as logged.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerationDecision {
+    pub review: Review,
+    pub status: ModerationStatus,
+    pub moderator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub flags: Vec<ModerationFlag>,
+    pub decided_at: String,
+}
+
+/// Catalog entry for the `product_id` reviews refer to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Product {
+    pub id: String,
+    #[serde(flatten)]
+    pub fields: ProductFields,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// The editable part of a product.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProductFields {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProductRequest {
+    pub id: String,
+    #[serde(flatten)]
+    pub fields: ProductFields,
+}
+
+/// Query parameters of `GET /products`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductQuery {
+    pub category: Option<String>,
+    pub brand: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProductImportReport {
+    pub created: usize,
+    pub updated: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,22 +128,623 @@ pub struct InsertReviewRequest {
     pub review_rating: u8,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SearchRequest {
     pub query: String,
     pub limit: Option<usize>,
+    pub chunk_aggregation: Option<ChunkAggregation>,
+    pub field_weights: Option<FieldWeights>,
+    #[serde(flatten)]
+    pub filters: ReviewFilters,
+    /// Between 0 (rank by relevance only) and 1 (favour results unlike the
+    /// ones already picked). Enables maximal-marginal-relevance re-ranking.
+    pub diversity: Option<f32>,
+    /// Collapse results into groups instead of returning individual reviews.
+    pub group_by: Option<GroupBy>,
+    /// Maximum number of groups returned.
+    pub groups: Option<usize>,
+    /// Maximum number of reviews shown per group.
+    pub per_group: Option<usize>,
+    /// Overrides of the server's ranking configuration for this request.
+    pub ranking: Option<RankingOptions>,
+    /// Attributes to count over the filtered candidates.
+    pub facets: Option<Vec<Facet>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Facet {
+    ReviewRating,
+    ProductId,
+    /// Month the review was written, as `YYYY-MM` in UTC.
+    Month,
+    Labels,
+}
+
+impl Facet {
+    pub fn name(self) -> &'static str {
+        match self {
+            Facet::ReviewRating => "review_rating",
+            Facet::ProductId => "product_id",
+            Facet::Month => "month",
+            Facet::Labels => "labels",
+        }
+    }
+}
+
+/// How many candidates have one value of a facet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FacetBucket {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Per-request ranking overrides; unset values use the server configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RankingOptions {
+    pub semantic_weight: Option<f32>,
+    pub recency_weight: Option<f32>,
+    pub recency_half_life_days: Option<f32>,
+    pub rating_weight: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    ProductId,
+}
+
+/// Restrictions on which reviews a search or saved alert matches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewFilters {
+    /// Only match reviews detected as one of these languages, given as
+    /// ISO 639-3 codes or English names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// Only match reviews whose sentiment falls in this range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<SentimentRange>,
+    /// Only match reviews carrying at least one of these labels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// Only match reviews of catalog products in this category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Only match reviews of catalog products of this brand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+}
+
+/// Inclusive sentiment bounds; either end may be left open.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SentimentRange {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl SentimentRange {
+    pub fn contains(&self, sentiment: f32) -> bool {
+        self.min.is_none_or(|min| sentiment >= min) && self.max.is_none_or(|max| sentiment <= max)
+    }
+}
+
+/// Relative weight of title and body similarity when fusing them into one
+/// review score. Weights don't need to add up to one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FieldWeights {
+    pub title: f32,
+    pub body: f32,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        Self {
+            title: 0.3,
+            body: 0.7,
+        }
+    }
+}
+
+impl FieldWeights {
+    pub fn fuse(&self, title_score: f32, body_score: f32) -> f32 {
+        let title = self.title.max(0.0);
+        let body = self.body.max(0.0);
+        if title + body <= 0.0 {
+            return FieldWeights::default().fuse(title_score, body_score);
+        }
+
+        (title * title_score + body * body_score) / (title + body)
+    }
+}
+
+/// How the scores of a review's matching chunks become the review's score.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum ChunkAggregation {
+    /// Score of the best chunk.
+    #[default]
+    Max,
+    /// Sum of the `n` best chunk scores, favouring reviews that match in
+    /// several places.
+    SumTopN { n: usize },
+}
+
+impl ChunkAggregation {
+    /// Combines chunk scores that are already sorted best first.
+    pub fn combine(&self, sorted_scores: &[f32]) -> f32 {
+        match *self {
+            ChunkAggregation::Max => sorted_scores.first().copied().unwrap_or(0.0),
+            ChunkAggregation::SumTopN { n } => sorted_scores.iter().take(n.max(1)).sum(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub reviews: Vec<ReviewWithScore>,
     pub total_found: usize,
+    /// Set instead of `reviews` when the request has `group_by`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub groups: Option<Vec<ReviewGroup>>,
+    /// Buckets for each requested facet, counted over every candidate that
+    /// passed the filters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facets: Option<BTreeMap<String, Vec<FacetBucket>>>,
+}
+
+/// Search hits sharing a `group_by` value.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewGroup {
+    pub key: String,
+    pub best_score: f32,
+    /// Matching reviews in the group among the ranked candidates.
+    pub matching_reviews: usize,
+    pub mean_rating: f32,
+    /// The group's best reviews, at most `per_group`.
+    pub reviews: Vec<ReviewWithScore>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReviewWithScore {
     pub review: Review,
     pub similarity_score: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field_scores: Option<FieldScores>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<ScoreBreakdown>,
+    /// Chunks of `review_body` that matched the query, best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_chunks: Vec<ChunkMatch>,
+    /// Topic cluster from the latest `/analytics/clusters` run, if the review
+    /// was part of it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_id: Option<usize>,
+    /// Catalog entry of the review's product, if the catalog has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
+}
+
+/// Title similarity and aggregated body similarity before fusion.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FieldScores {
+    pub title: f32,
+    pub body: f32,
+}
+
+/// Parts of a ranked score. `final_score` is what results are ordered by and
+/// what `similarity_score` reports.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// Fused title and body similarity.
+    pub semantic: f32,
+    pub recency_boost: f32,
+    pub rating_boost: f32,
+    pub final_score: f32,
+}
+
+/// Byte offsets of a matching chunk within `review_body`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkMatch {
+    pub start: usize,
+    pub end: usize,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAlertRequest {
+    pub query: String,
+    /// Minimum fused title/body similarity for a new review to match.
+    pub threshold: f32,
+    #[serde(flatten)]
+    pub filters: ReviewFilters,
+    /// Matches are POSTed here as JSON when set.
+    pub webhook_url: Option<String>,
+}
+
+/// Defines a zero-shot label by example sentences.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateLabelRequest {
+    pub name: String,
+    pub prototypes: Vec<String>,
+    pub threshold: Option<f32>,
+}
+
+/// A review category assigned by similarity to its prototype sentences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub name: String,
+    pub prototypes: Vec<String>,
+    /// Minimum cosine similarity between a review vector and a prototype.
+    pub threshold: f32,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelBackfillReport {
+    pub reviews: usize,
+    pub labeled_reviews: usize,
+    /// Reviews per label.
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// A saved search that new reviews are matched against as they come in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub query: String,
+    pub threshold: f32,
+    #[serde(flatten)]
+    pub filters: ReviewFilters,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    pub created_at: String,
+}
+
+/// A new review that matched a saved alert. This is also the webhook payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertMatch {
+    pub id: String,
+    pub alert_id: String,
+    pub query: String,
+    pub score: f32,
+    pub review: Review,
+    pub matched_at: String,
+}
+
+/// Where a search hit came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitSource {
+    /// Returned by the approximate nearest neighbour index.
+    Ann,
+    /// Added by the exact scan that runs when filters leave too few candidates.
+    FallbackScan,
+}
+
+/// Outcome of one filter for one review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterDecision {
+    pub filter: String,
+    /// The review's value for the filtered attribute, if it has one.
+    pub value: Option<String>,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewField {
+    Title,
+    Body,
+}
+
+/// Scores of one stored vector of a hit against the query.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VectorScore {
+    pub vector_id: i32,
+    pub field: ReviewField,
+    /// Byte range of the body chunk, for body vectors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<usize>,
+    /// Raw score reported by the index, if this vector was among its
+    /// candidates: a distance or a similarity depending on the SPFresh
+    /// wrapper, so it is only used to pick candidates.
+    pub ann_distance: Option<f32>,
+    /// Cosine similarity recomputed from the stored vector.
+    pub exact_cosine: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainedHit {
+    pub review: Review,
+    pub similarity_score: f32,
+    pub field_scores: FieldScores,
+    pub score_breakdown: Option<ScoreBreakdown>,
+    pub source: HitSource,
+    /// Raw index score of the review's closest candidate vector.
+    pub ann_distance: Option<f32>,
+    /// The final score the review would get from exact cosines of all its
+    /// vectors.
+    pub exact_score: f32,
+    pub vectors: Vec<VectorScore>,
+    pub filters: Vec<FilterDecision>,
+}
+
+/// An index candidate that a filter removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedCandidate {
+    pub review_id: String,
+    pub filters: Vec<FilterDecision>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainResponse {
+    pub query: String,
+    pub reviews: Vec<ExplainedHit>,
+    pub rejected: Vec<RejectedCandidate>,
+    /// Number of vectors the index returned for the query.
+    pub ann_candidates: usize,
+    pub fallback_scan: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterMethod {
+    #[default]
+    Kmeans,
+    /// k-means with a penalty on oversized clusters, in the spirit of the
+    /// balanced k-means SPTAG uses to build its trees.
+    Balanced,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClusterRequest {
+    pub k: Option<usize>,
+    #[serde(default)]
+    pub method: ClusterMethod,
+    pub max_iterations: Option<usize>,
+    /// Only cluster reviews of these products.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_ids: Option<Vec<String>>,
+    /// RFC 3339 start of the time range, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// RFC 3339 end of the time range, exclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+}
+
+/// One clustering job. Each run replaces the assignments of the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterRun {
+    pub id: String,
+    pub created_at: String,
+    pub model: String,
+    pub request: ClusterRequest,
+    pub reviews_clustered: usize,
+    pub iterations: usize,
+    pub clusters: Vec<ClusterSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterSummary {
+    pub cluster_id: usize,
+    pub size: usize,
+    /// The top distinctive terms, joined.
+    pub label: String,
+    /// Terms far more common in this cluster than in the clustered reviews
+    /// overall, most distinctive first.
+    pub top_terms: Vec<String>,
+    /// Reviews closest to the cluster centroid, closest first.
+    pub central_reviews: Vec<CentralReview>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CentralReview {
+    pub review_id: String,
+    pub review_title: String,
+    pub product_id: String,
+    /// Cosine similarity to the centroid.
+    pub similarity: f32,
+}
+
+/// Query parameters of `GET /products/{id}/summary`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductSummaryQuery {
+    /// Representative reviews per polarity, 3 by default.
+    pub representatives: Option<usize>,
+}
+
+/// Digest of a product's reviews for product pages.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductSummary {
+    pub product_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
+    pub reviews: usize,
+    pub average_rating: f32,
+    /// Review count per star rating, 1 to 5.
+    pub rating_histogram: BTreeMap<u8, usize>,
+    /// 4-5★ reviews closest to the centroid of the 4-5★ reviews, closest first.
+    pub positive: Vec<RepresentativeReview>,
+    /// 1-2★ reviews closest to the centroid of the 1-2★ reviews, closest first.
+    pub negative: Vec<RepresentativeReview>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepresentativeReview {
+    pub review: Review,
+    /// Cosine similarity to the centroid of its group.
+    pub similarity: f32,
+}
+
+/// Query parameters of `GET /products/{id}/similar`.
+#[derive(Debug, Default, Deserialize)]
+pub struct SimilarProductsQuery {
+    pub limit: Option<usize>,
+}
+
+/// A product whose reviews describe it like another product's do.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimilarProduct {
+    pub product_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<Product>,
+    /// Cosine similarity of the two product embeddings.
+    pub similarity: f32,
+    /// Reviews the product's embedding is averaged over.
+    pub reviews: usize,
+}
+
+/// Query parameters of `GET /analytics/duplicates`.
+#[derive(Debug, Default, Deserialize)]
+pub struct DuplicateQuery {
+    /// Overrides the configured duplicate similarity threshold.
+    pub threshold: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    pub threshold: f32,
+    /// Reviews in some duplicate cluster.
+    pub duplicate_reviews: usize,
+    /// Largest clusters first.
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+/// Reviews linked by chains of near-duplicate pairs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    /// Distinct products the copies were posted under.
+    pub products: usize,
+    /// Lowest similarity of the pairs holding the cluster together.
+    pub min_similarity: f32,
+    /// Oldest first.
+    pub reviews: Vec<DuplicateMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateMember {
+    pub review_id: String,
+    pub product_id: String,
+    pub review_title: String,
+    pub timestamp: String,
+}
+
+/// Body of the 422 returned when a review is rejected for personal data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PiiRejection {
+    pub detectors: Vec<String>,
+}
+
+/// Body of the 409 returned when a review is rejected as a near-duplicate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateConflict {
+    pub duplicate_of: String,
+    pub similarity: f32,
+}
+
+/// What happened to one review of a bulk insert.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkInsertStatus {
+    /// Stored and searchable.
+    Inserted,
+    /// Stored in the moderation queue.
+    Pending,
+    /// Refused as a near-duplicate of `duplicate_of`.
+    Duplicate,
+    /// Refused for personal data found by `detectors`.
+    Rejected,
+}
+
+/// Outcome of one review of `POST /reviews/bulk`, in request order. Carries
+/// the review when it was stored, the same fields as the single insert's 409
+/// or 422 body when it wasn't, and the reason as `error`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkInsertResult {
+    pub status: BulkInsertStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<Review>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detectors: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What a product's reviews say about each configured aspect.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductAspects {
+    pub product_id: String,
+    pub reviews: usize,
+    /// Aspects with at least one mention, most mentioned first.
+    pub aspects: Vec<AspectSentiment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AspectSentiment {
+    pub aspect: String,
+    /// Review clauses matched to the aspect.
+    pub mentions: usize,
+    pub mean_sentiment: f32,
+    pub positive: usize,
+    pub neutral: usize,
+    pub negative: usize,
+    /// The most positive mentions followed by the most negative ones.
+    pub snippets: Vec<AspectSnippet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AspectSnippet {
+    pub review_id: String,
+    pub text: String,
+    pub sentiment: f32,
+    /// Cosine similarity of the clause to the aspect.
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub total_reviews: usize,
+    pub total_vectors: usize,
+    pub embedding_cache: Option<CacheStats>,
+    pub sentiment: SentimentStats,
+}
+
+/// How review text sentiment lines up with star ratings.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SentimentStats {
+    pub mean: f32,
+    /// Reviews of at most 2 stars with positive text.
+    pub positive_text_low_rating: usize,
+    /// Reviews of at least 4 stars with negative text.
+    pub negative_text_high_rating: usize,
+    /// Text polarity counts for each star rating, 1 to 5.
+    pub by_rating: Vec<RatingSentiment>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RatingSentiment {
+    pub rating: u8,
+    pub reviews: usize,
+    pub mean_sentiment: f32,
+    pub negative: usize,
+    pub neutral: usize,
+    pub positive: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub memory_entries: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::models::{ModerationDecision, ModerationFlag, ModerationItem, Review};
+
+const QUEUE_FILE: &str = "queue.json";
+const DECISIONS_FILE: &str = "decisions.jsonl";
+
+/// Title and body shorter than this, in characters, get `ShortText`.
+const MIN_TEXT_CHARS: usize = 20;
+
+/// Letters a review needs before `AllCaps` applies, so "OK" isn't shouting.
+const MIN_CAPS_LETTERS: usize = 10;
+
+const LINK_MARKERS: &[&str] = &["http://", "https://", "www."];
+
+/// Automatic pre-flags of a new review. `duplicate_body` tells whether a
+/// stored or queued review already has the same body, compared with
+/// `normalize_body`.
+pub fn pre_flags(review: &Review, duplicate_body: bool) -> Vec<ModerationFlag> {
+    let text = format!("{} {}", review.review_title, review.review_body);
+    let mut flags = Vec::new();
+
+    if text.trim().chars().count() < MIN_TEXT_CHARS {
+        flags.push(ModerationFlag::ShortText);
+    }
+
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let cased = letters.iter().filter(|c| c.is_uppercase() || c.is_lowercase()).count();
+    if cased >= MIN_CAPS_LETTERS && letters.iter().all(|c| !c.is_lowercase()) {
+        flags.push(ModerationFlag::AllCaps);
+    }
+
+    let lowercase = text.to_lowercase();
+    if LINK_MARKERS.iter().any(|marker| lowercase.contains(marker)) {
+        flags.push(ModerationFlag::Link);
+    }
+
+    if duplicate_body {
+        flags.push(ModerationFlag::DuplicateBody);
+    }
+
+    flags
+}
+
+/// Lowercased body with whitespace collapsed, for exact duplicate checks.
+pub fn normalize_body(body: &str) -> String {
+    body.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reviews waiting for a moderator in `data_dir/moderation/queue.json`, and
+/// the log of decisions taken in `decisions.jsonl` next to it.
+pub struct ModerationStore {
+    dir: PathBuf,
+    queue: RwLock<Vec<ModerationItem>>,
+}
+
+impl ModerationStore {
+    pub fn load(data_dir: &str) -> Result<Self> {
+        let dir = Path::new(data_dir).join("moderation");
+        let path = dir.join(QUEUE_FILE);
+
+        let queue = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            dir,
+            queue: RwLock::new(queue),
+        })
+    }
+
+    /// Pending reviews, oldest first.
+    pub async fn queue(&self) -> Vec<ModerationItem> {
+        self.queue.read().await.clone()
+    }
+
+    /// Whether a queued review has this normalized body.
+    pub async fn has_body(&self, body: &str) -> bool {
+        self.queue
+            .read()
+            .await
+            .iter()
+            .any(|item| normalize_body(&item.review.review_body) == body)
+    }
+
+    pub async fn enqueue(&self, item: ModerationItem) -> Result<()> {
+        let mut queue = self.queue.write().await;
+        queue.push(item);
+        self.save(&queue)
+    }
+
+    /// Removes a review from the queue so only one moderator decides on it.
+    pub async fn take(&self, review_id: &str) -> Result<Option<ModerationItem>> {
+        let mut queue = self.queue.write().await;
+        let Some(position) = queue.iter().position(|item| item.review.id == review_id) else {
+            return Ok(None);
+        };
+
+        let item = queue.remove(position);
+        self.save(&queue)?;
+        Ok(Some(item))
+    }
+
+    /// Puts back a review taken from the queue whose decision failed.
+    pub async fn restore(&self, item: ModerationItem) -> Result<()> {
+        let mut queue = self.queue.write().await;
+        let position = queue
+            .iter()
+            .position(|queued| queued.review.timestamp > item.review.timestamp)
+            .unwrap_or(queue.len());
+        queue.insert(position, item);
+        self.save(&queue)
+    }
+
+    pub fn record_decision(&self, decision: &ModerationDecision) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(DECISIONS_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(decision)?)?;
+
+        Ok(())
+    }
+
+    fn save(&self, queue: &[ModerationItem]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let path = self.dir.join(QUEUE_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(queue)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+}
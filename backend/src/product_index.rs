@@ -0,0 +1,266 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::spfresh_bindings::SPFreshIndexWrapper;
+use crate::vector_store::cosine_similarity;
+
+/// Directory of the product index, inside the review index's directory.
+const PRODUCT_INDEX_DIR: &str = "product_index";
+
+/// Superseded vectors tolerated before the index is compacted, on top of
+/// one per live product.
+const MIN_COMPACT_UNMAPPED: usize = 64;
+
+/// A product's running sum of normalized review vectors and the index vector
+/// currently standing for it.
+struct ProductVector {
+    sum: Vec<f32>,
+    reviews: usize,
+    embedding: Vec<f32>,
+    vector_id: Option<i32>,
+}
+
+/// The index and its bookkeeping, behind one lock so adds never overlap a
+/// search.
+#[derive(Default)]
+struct ProductVectors {
+    index: Option<SPFreshIndexWrapper>,
+    dir: PathBuf,
+    products: HashMap<String, ProductVector>,
+    vector_products: HashMap<i32, String>,
+}
+
+/// Product embeddings, each the mean of the product's normalized review
+/// vectors, in a secondary SPFresh index with its own data directory. Like
+/// the review index it is append-only: when a review is added or removed the
+/// product gets a fresh vector and the old one is left behind unmapped. Once
+/// the unmapped vectors outnumber the products the index is rebuilt with one
+/// vector per product, so it doesn't grow with churn.
+pub struct ProductIndex {
+    dimension: usize,
+    vectors: RwLock<ProductVectors>,
+}
+
+impl ProductIndex {
+    /// Creates an empty product index under `index_dir`. Like the review
+    /// index it is filled as stored reviews are loaded.
+    pub fn new(index_dir: &Path, dimension: usize) -> Self {
+        let dir = index_dir.join(PRODUCT_INDEX_DIR);
+        let index = match SPFreshIndexWrapper::empty_at(dimension, &dir) {
+            Ok(index) => Some(index),
+            Err(e) => {
+                eprintln!("Failed to create SPFresh product index: {}", e);
+                None
+            }
+        };
+
+        Self {
+            dimension,
+            vectors: RwLock::new(ProductVectors {
+                index,
+                dir,
+                ..ProductVectors::default()
+            }),
+        }
+    }
+
+    /// Drops the index handle, which saves the index file.
+    pub async fn close(&self) {
+        self.vectors.write().await.index = None;
+    }
+
+    /// Opens the index again from the files saved under `index_dir`, after
+    /// `close` and a move of the directory. Vector ids are positions in the
+    /// file, so the product mappings still hold.
+    pub async fn reopen(&self, index_dir: &Path) -> Result<()> {
+        let dir = index_dir.join(PRODUCT_INDEX_DIR);
+        let index = SPFreshIndexWrapper::with_data_dir(self.dimension, &dir.to_string_lossy())
+            .map_err(|e| anyhow::anyhow!("Failed to open SPFresh product index: {}", e))?;
+
+        let mut vectors = self.vectors.write().await;
+        vectors.index = Some(index);
+        vectors.dir = dir;
+        Ok(())
+    }
+
+    /// Rebuilds the index with only the products' current vectors, if any
+    /// were superseded. Run once stored reviews are loaded, since loading
+    /// gives a product a new vector for every review.
+    pub async fn compact(&self) -> Result<()> {
+        let mut vectors = self.vectors.write().await;
+        if vectors.unmapped() > 0 {
+            vectors.compact(self.dimension)?;
+        }
+        Ok(())
+    }
+
+    /// Vectors in the index, current and superseded.
+    #[cfg(test)]
+    pub async fn vector_count(&self) -> usize {
+        let vectors = self.vectors.read().await;
+        vectors.index.as_ref().map_or(0, |index| index.get_vector_count())
+    }
+
+    /// Folds a review's vector into its product's embedding.
+    pub async fn add_review(&self, product_id: &str, review_vector: &[f32]) -> Result<()> {
+        let Some(review_vector) = normalized(review_vector) else {
+            return Ok(());
+        };
+
+        let mut vectors = self.vectors.write().await;
+        let product = vectors
+            .products
+            .entry(product_id.to_string())
+            .or_insert_with(|| ProductVector {
+                sum: vec![0.0; review_vector.len()],
+                reviews: 0,
+                embedding: Vec::new(),
+                vector_id: None,
+            });
+        for (sum, value) in product.sum.iter_mut().zip(&review_vector) {
+            *sum += value;
+        }
+        product.reviews += 1;
+
+        vectors.reindex(product_id)?;
+        vectors.compact_if_churned(self.dimension)
+    }
+
+    /// Takes a deleted review's vector back out of its product's embedding,
+    /// dropping the product once it has no reviews left.
+    pub async fn remove_review(&self, product_id: &str, review_vector: &[f32]) -> Result<()> {
+        let Some(review_vector) = normalized(review_vector) else {
+            return Ok(());
+        };
+
+        let mut vectors = self.vectors.write().await;
+        let Some(product) = vectors.products.get_mut(product_id) else {
+            return Ok(());
+        };
+        for (sum, value) in product.sum.iter_mut().zip(&review_vector) {
+            *sum -= value;
+        }
+        product.reviews -= 1;
+
+        if product.reviews == 0 {
+            if let Some(vector_id) = product.vector_id {
+                vectors.vector_products.remove(&vector_id);
+            }
+            vectors.products.remove(product_id);
+        } else {
+            vectors.reindex(product_id)?;
+        }
+
+        vectors.compact_if_churned(self.dimension)
+    }
+
+    /// Up to `limit` other products whose embeddings are closest to the
+    /// product's, most similar first, with their similarity and review count.
+    /// `None` if the product has no indexed reviews.
+    pub async fn similar(&self, product_id: &str, limit: usize) -> Result<Option<Vec<(String, f32, usize)>>> {
+        let vectors = self.vectors.read().await;
+        let Some(product) = vectors.products.get(product_id) else {
+            return Ok(None);
+        };
+        let Some(index) = &vectors.index else {
+            return Err(anyhow::anyhow!("SPFresh product index not initialized"));
+        };
+        if product.embedding.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+
+        // Superseded vectors and the product itself take up result slots
+        let results = index
+            .search(&product.embedding, limit + 1 + vectors.unmapped())
+            .map_err(|e| anyhow::anyhow!("Product index search failed: {}", e))?;
+
+        let mut similar: Vec<(String, f32, usize)> = results
+            .into_iter()
+            .filter_map(|(vector_id, _)| vectors.vector_products.get(&vector_id))
+            .filter(|other| other.as_str() != product_id)
+            .filter_map(|other| {
+                let found = vectors.products.get(other)?;
+                let similarity = cosine_similarity(&product.embedding, &found.embedding);
+                Some((other.clone(), similarity, found.reviews))
+            })
+            .collect();
+        similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+        similar.truncate(limit);
+
+        Ok(Some(similar))
+    }
+}
+
+impl ProductVectors {
+    /// Adds the product's current embedding to the index and points the
+    /// product at it.
+    fn reindex(&mut self, product_id: &str) -> Result<()> {
+        let Some(product) = self.products.get_mut(product_id) else {
+            return Ok(());
+        };
+        // The mean and the sum point the same way
+        product.embedding = normalized(&product.sum).unwrap_or_default();
+
+        let Some(index) = &mut self.index else {
+            return Ok(());
+        };
+        if product.embedding.is_empty() {
+            return Ok(());
+        }
+        let vector_id = index
+            .add_vector(&product.embedding, Some(product_id))
+            .map_err(|e| anyhow::anyhow!("Failed to add vector to SPFresh product index: {}", e))?;
+
+        if let Some(old_id) = product.vector_id.replace(vector_id) {
+            self.vector_products.remove(&old_id);
+        }
+        self.vector_products.insert(vector_id, product_id.to_string());
+
+        Ok(())
+    }
+
+    /// Vectors in the index that no product points at anymore.
+    fn unmapped(&self) -> usize {
+        let count = self.index.as_ref().map_or(0, |index| index.get_vector_count());
+        count.saturating_sub(self.vector_products.len())
+    }
+
+    fn compact_if_churned(&mut self, dimension: usize) -> Result<()> {
+        if self.unmapped() > self.products.len().max(MIN_COMPACT_UNMAPPED) {
+            self.compact(dimension)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the index with a fresh one holding each product's current
+    /// embedding.
+    fn compact(&mut self, dimension: usize) -> Result<()> {
+        // The old handle saves its file when dropped, so it has to go before
+        // the directory is cleared
+        if self.index.take().is_none() {
+            return Ok(());
+        }
+        let index = SPFreshIndexWrapper::empty_at(dimension, &self.dir)
+            .map_err(|e| anyhow::anyhow!("Failed to recreate SPFresh product index: {}", e))?;
+        self.index = Some(index);
+
+        self.vector_products.clear();
+        let product_ids: Vec<String> = self.products.keys().cloned().collect();
+        for product_id in product_ids {
+            if let Some(product) = self.products.get_mut(&product_id) {
+                product.vector_id = None;
+            }
+            self.reindex(&product_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `vector` scaled to unit length, or `None` if it is all zeros.
+fn normalized(vector: &[f32]) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    (norm > 0.0).then(|| vector.iter().map(|x| x / norm).collect())
+}
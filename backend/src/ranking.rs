@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::{RankingOptions, Review, ScoreBreakdown};
+
+/// How the semantic score is combined with review metadata after retrieval.
+/// The final score is `semantic_weight * semantic + recency_weight *
+/// recency_boost + rating_weight * rating_boost`.
+#[derive(Debug, Clone, Copy)]
+pub struct RankingConfig {
+    pub semantic_weight: f32,
+    /// Weight of the time-decay boost. 0 leaves recency out of ranking.
+    pub recency_weight: f32,
+    /// Age at which a review's recency boost has dropped to one half.
+    pub recency_half_life_days: f32,
+    /// Weight of the rating boost. Positive favours high ratings, negative
+    /// favours low ones.
+    pub rating_weight: f32,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            semantic_weight: 1.0,
+            recency_weight: 0.0,
+            recency_half_life_days: 180.0,
+            rating_weight: 0.0,
+        }
+    }
+}
+
+impl RankingConfig {
+    /// Applies the per-request values that are set.
+    pub fn with_overrides(mut self, options: Option<&RankingOptions>) -> Self {
+        let Some(options) = options else {
+            return self;
+        };
+
+        if let Some(weight) = options.semantic_weight {
+            self.semantic_weight = weight;
+        }
+        if let Some(weight) = options.recency_weight {
+            self.recency_weight = weight;
+        }
+        if let Some(days) = options.recency_half_life_days {
+            self.recency_half_life_days = days;
+        }
+        if let Some(weight) = options.rating_weight {
+            self.rating_weight = weight;
+        }
+
+        self
+    }
+
+    pub fn score(&self, semantic: f32, review: &Review, now: DateTime<Utc>) -> ScoreBreakdown {
+        let recency_boost = recency_boost(&review.timestamp, now, self.recency_half_life_days);
+        let rating_boost = rating_boost(review.review_rating);
+
+        ScoreBreakdown {
+            semantic,
+            recency_boost,
+            rating_boost,
+            final_score: self.semantic_weight * semantic
+                + self.recency_weight * recency_boost
+                + self.rating_weight * rating_boost,
+        }
+    }
+}
+
+/// `0.5 ^ (age / half_life)`: 1 for a review written now, 0.5 after one
+/// half-life. Unparseable timestamps get no boost.
+pub fn recency_boost(timestamp: &str, now: DateTime<Utc>, half_life_days: f32) -> f32 {
+    let Ok(written) = DateTime::parse_from_rfc3339(timestamp) else {
+        return 0.0;
+    };
+
+    let age_days = (now - written.with_timezone(&Utc)).num_seconds().max(0) as f32 / 86_400.0;
+    0.5f32.powf(age_days / half_life_days)
+}
+
+/// Maps a 1-5 star rating onto [-1, 1], with 3 stars neutral.
+pub fn rating_boost(rating: u8) -> f32 {
+    ((rating.clamp(1, 5) as f32 - 3.0) / 2.0).clamp(-1.0, 1.0)
+}
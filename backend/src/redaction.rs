@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use sha2::Sha256;
+use std::fmt;
+
+/// What happens to personal data found in a new review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionMode {
+    /// Store text as submitted.
+    #[default]
+    Off,
+    /// Replace each match with its detector name, e.g. `[EMAIL]`.
+    Mask,
+    /// Replace each match with its detector name and a short keyed hash
+    /// of the match, so repeats of the same value can still be told apart.
+    Hash,
+    /// Refuse the review with a `PiiRejected` error.
+    Reject,
+}
+
+pub fn parse_redaction_mode(value: &str) -> Result<RedactionMode> {
+    match value.to_ascii_lowercase().as_str() {
+        "off" => Ok(RedactionMode::Off),
+        "mask" => Ok(RedactionMode::Mask),
+        "hash" => Ok(RedactionMode::Hash),
+        "reject" => Ok(RedactionMode::Reject),
+        other => Err(anyhow::anyhow!(
+            "Unknown redaction mode '{}', expected 'off', 'mask', 'hash' or 'reject'",
+            other
+        )),
+    }
+}
+
+/// A named pattern for one kind of personal data. `validate` weeds out
+/// matches that only look right, e.g. digit runs failing a checksum.
+#[derive(Debug, Clone)]
+pub struct Detector {
+    pub name: String,
+    pattern: Regex,
+    validate: Option<fn(&str) -> bool>,
+}
+
+impl Detector {
+    fn built_in(name: &str, pattern: &str, validate: Option<fn(&str) -> bool>) -> Self {
+        Self {
+            name: name.to_string(),
+            pattern: Regex::new(pattern).expect("built-in redaction pattern"),
+            validate,
+        }
+    }
+}
+
+/// Detectors in priority order: where matches overlap, the earlier detector
+/// wins, so a card number isn't half-redacted as a phone number. Thai
+/// national IDs come before cards: about one in ten valid IDs also passes
+/// the Luhn check, while a 13-digit card is rare.
+fn built_in_detectors() -> Vec<Detector> {
+    vec![
+        Detector::built_in("email", r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b", None),
+        Detector::built_in(
+            "thai_national_id",
+            r"\b\d[ -]?\d{4}[ -]?\d{5}[ -]?\d{2}[ -]?\d\b",
+            Some(is_thai_national_id),
+        ),
+        Detector::built_in("credit_card", r"\b(?:\d[ -]?){12,18}\d\b", Some(passes_luhn)),
+        Detector::built_in(
+            "phone",
+            r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?|\b)\d{2,4}[ .-]?\d{3,4}[ .-]?\d{3,4}\b",
+            Some(|text| (9..=15).contains(&digits(text).len())),
+        ),
+    ]
+}
+
+/// Parses a custom detector given as `name=regex`.
+pub fn parse_detector(spec: &str) -> Result<Detector> {
+    let (name, pattern) = spec
+        .split_once('=')
+        .with_context(|| format!("Redaction pattern '{}' must look like name=regex", spec))?;
+    let name = name.trim();
+    anyhow::ensure!(!name.is_empty(), "Redaction pattern '{}' has no name", spec);
+
+    Ok(Detector {
+        name: name.to_string(),
+        pattern: Regex::new(pattern).with_context(|| format!("Invalid redaction pattern for '{}'", name))?,
+        validate: None,
+    })
+}
+
+/// Server secret keying the hashes of hash mode. Without it anyone could
+/// hash guessed emails or phone numbers and match them against stored
+/// reviews. Never printed.
+#[derive(Clone)]
+struct RedactionSecret(Vec<u8>);
+
+impl fmt::Debug for RedactionSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RedactionSecret(..)")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RedactionConfig {
+    pub mode: RedactionMode,
+    pub detectors: Vec<Detector>,
+    secret: Option<RedactionSecret>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            mode: RedactionMode::Off,
+            detectors: built_in_detectors(),
+            secret: None,
+        }
+    }
+}
+
+impl RedactionConfig {
+    /// The built-in detectors followed by `custom` ones. Hash mode needs a
+    /// non-empty `secret`; the same secret has to be used across restarts
+    /// for repeats to keep matching.
+    pub fn with_custom(mode: RedactionMode, custom: Vec<Detector>, secret: Option<&str>) -> Result<Self> {
+        let secret = secret.filter(|secret| !secret.is_empty());
+        anyhow::ensure!(
+            mode != RedactionMode::Hash || secret.is_some(),
+            "Redaction mode 'hash' needs a secret (--redact-secret or REDACT_SECRET)"
+        );
+
+        let mut detectors = built_in_detectors();
+        for detector in custom {
+            anyhow::ensure!(
+                detectors.iter().all(|existing| existing.name != detector.name),
+                "Redaction pattern '{}' is defined twice",
+                detector.name
+            );
+            detectors.push(detector);
+        }
+
+        Ok(Self {
+            mode,
+            detectors,
+            secret: secret.map(|secret| RedactionSecret(secret.as_bytes().to_vec())),
+        })
+    }
+
+    /// Replaces personal data in `text` according to the mode, adding the
+    /// names of the detectors that fired to `fired`. Text is returned
+    /// unchanged when the mode is `Off` or `Reject`.
+    pub fn redact(&self, text: &str, fired: &mut Vec<String>) -> String {
+        if self.mode == RedactionMode::Off {
+            return text.to_string();
+        }
+
+        // Every detector looks at the original text, so replacements never
+        // feed later detectors
+        let mut spans: Vec<(usize, usize, &Detector)> = Vec::new();
+        for detector in &self.detectors {
+            for found in detector.pattern.find_iter(text) {
+                if detector.validate.is_some_and(|validate| !validate(found.as_str())) {
+                    continue;
+                }
+                let overlaps = spans
+                    .iter()
+                    .any(|(start, end, _)| found.start() < *end && *start < found.end());
+                if !overlaps && !found.as_str().is_empty() {
+                    spans.push((found.start(), found.end(), detector));
+                }
+            }
+        }
+        spans.sort_by_key(|(start, _, _)| *start);
+
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, detector) in spans {
+            if !fired.contains(&detector.name) {
+                fired.push(detector.name.clone());
+            }
+            redacted.push_str(&text[last..start]);
+            redacted.push_str(&self.replacement(&detector.name, &text[start..end]));
+            last = end;
+        }
+        redacted.push_str(&text[last..]);
+
+        if self.mode == RedactionMode::Reject {
+            return text.to_string();
+        }
+        redacted
+    }
+
+    fn replacement(&self, name: &str, found: &str) -> String {
+        let label = name.to_uppercase();
+        match (self.mode, &self.secret) {
+            (RedactionMode::Hash, Some(secret)) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&secret.0).expect("HMAC takes keys of any length");
+                mac.update(found.as_bytes());
+                let hash: String = mac
+                    .finalize()
+                    .into_bytes()
+                    .iter()
+                    .take(6)
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect();
+                format!("[{}:{}]", label, hash)
+            }
+            _ => format!("[{}]", label),
+        }
+    }
+}
+
+/// Returned by `insert_review` when redaction is in reject mode and a review
+/// contains personal data.
+#[derive(Debug, Clone)]
+pub struct PiiRejected {
+    pub detectors: Vec<String>,
+}
+
+impl fmt::Display for PiiRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Review contains personal data: {}", self.detectors.join(", "))
+    }
+}
+
+impl std::error::Error for PiiRejected {}
+
+fn digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Luhn checksum used by payment card numbers.
+fn passes_luhn(text: &str) -> bool {
+    let digits = digits(text);
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Thai national ID check digit: the first 12 digits weighted 13 down to 2.
+fn is_thai_national_id(text: &str) -> bool {
+    let digits = digits(text);
+    if digits.len() != 13 {
+        return false;
+    }
+
+    let sum: u32 = digits[..12]
+        .iter()
+        .enumerate()
+        .map(|(i, &digit)| digit * (13 - i as u32))
+        .sum();
+    (11 - sum % 11) % 10 == digits[12]
+}
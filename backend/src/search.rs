@@ -0,0 +1,298 @@
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::chunking::ChunkSpan;
+use crate::language::normalize_language;
+use crate::models::{
+    ChunkMatch, Facet, FacetBucket, FilterDecision, GroupBy, Review, ReviewFilters, ReviewGroup, ReviewWithScore,
+    SearchRequest, SearchResponse, SentimentRange,
+};
+use crate::vector_store::cosine_similarity;
+
+const DEFAULT_GROUPS: usize = 10;
+const DEFAULT_PER_GROUP: usize = 3;
+
+/// Ranked reviews considered per group slot, so group counts and mean
+/// ratings reflect more than the reviews that end up shown.
+const CANDIDATES_PER_GROUP_SLOT: usize = 5;
+
+/// Number of ranked reviews a grouped search needs.
+pub fn grouped_candidates(request: &SearchRequest) -> usize {
+    let groups = request.groups.unwrap_or(DEFAULT_GROUPS);
+    let per_group = request.per_group.unwrap_or(DEFAULT_PER_GROUP);
+    groups * per_group * CANDIDATES_PER_GROUP_SLOT
+}
+
+/// Shapes ranked hits into the `/search` response, collapsing them into
+/// groups when the request asks for it.
+pub fn build_response(
+    request: &SearchRequest,
+    hits: Vec<ReviewWithScore>,
+    facets: Option<BTreeMap<String, Vec<FacetBucket>>>,
+) -> SearchResponse {
+    let Some(group_by) = request.group_by else {
+        return SearchResponse {
+            total_found: hits.len(),
+            reviews: hits,
+            groups: None,
+            facets,
+        };
+    };
+
+    let total_found = hits.len();
+    let groups = group_hits(
+        hits,
+        group_by,
+        request.groups.unwrap_or(DEFAULT_GROUPS),
+        request.per_group.unwrap_or(DEFAULT_PER_GROUP),
+    );
+
+    SearchResponse {
+        reviews: Vec::new(),
+        total_found,
+        groups: Some(groups),
+        facets,
+    }
+}
+
+/// Counts each facet's values over `reviews`. Ratings come highest first and
+/// months oldest first; product ids and labels largest bucket first.
+pub fn facet_counts<'a>(
+    facets: &[Facet],
+    reviews: impl Iterator<Item = &'a Review> + Clone,
+) -> BTreeMap<String, Vec<FacetBucket>> {
+    facets
+        .iter()
+        .map(|&facet| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for review in reviews.clone() {
+                for value in facet_values(facet, review) {
+                    *counts.entry(value).or_default() += 1;
+                }
+            }
+
+            let mut buckets: Vec<FacetBucket> = counts
+                .into_iter()
+                .map(|(value, count)| FacetBucket { value, count })
+                .collect();
+            match facet {
+                Facet::ReviewRating => buckets.sort_by(|a, b| b.value.cmp(&a.value)),
+                Facet::Month => buckets.sort_by(|a, b| a.value.cmp(&b.value)),
+                Facet::ProductId | Facet::Labels => {
+                    buckets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)))
+                }
+            }
+            (facet.name().to_string(), buckets)
+        })
+        .collect()
+}
+
+fn facet_values(facet: Facet, review: &Review) -> Vec<String> {
+    match facet {
+        Facet::ReviewRating => vec![review.review_rating.to_string()],
+        Facet::ProductId => vec![review.product_id.clone()],
+        Facet::Month => DateTime::parse_from_rfc3339(&review.timestamp)
+            .map(|written| written.with_timezone(&Utc).format("%Y-%m").to_string())
+            .into_iter()
+            .collect(),
+        Facet::Labels => review.labels.clone(),
+    }
+}
+
+/// Buckets hits by `group_by`, keeping every bucket's match count and mean
+/// rating but only its `per_group` best reviews. Groups are ordered by their
+/// best score. `hits` must already be ranked best first.
+fn group_hits(hits: Vec<ReviewWithScore>, group_by: GroupBy, groups: usize, per_group: usize) -> Vec<ReviewGroup> {
+    let mut order = Vec::new();
+    let mut buckets: HashMap<String, ReviewGroup> = HashMap::new();
+    let mut rating_sums: HashMap<String, u32> = HashMap::new();
+
+    for hit in hits {
+        let key = match group_by {
+            GroupBy::ProductId => hit.review.product_id.clone(),
+        };
+
+        let group = buckets.entry(key.clone()).or_insert_with(|| {
+            order.push(key.clone());
+            ReviewGroup {
+                key: key.clone(),
+                best_score: hit.similarity_score,
+                matching_reviews: 0,
+                mean_rating: 0.0,
+                reviews: Vec::new(),
+            }
+        });
+
+        let rating_sum = rating_sums.entry(key).or_default();
+        *rating_sum += hit.review.review_rating as u32;
+        group.matching_reviews += 1;
+        group.mean_rating = *rating_sum as f32 / group.matching_reviews as f32;
+        if group.reviews.len() < per_group {
+            group.reviews.push(hit);
+        }
+    }
+
+    // Hits arrive best first, so first-seen order is best-score order
+    order
+        .into_iter()
+        .take(groups)
+        .filter_map(|key| buckets.remove(&key))
+        .collect()
+}
+
+/// Review filters of a search or alert, normalized once up front. Catalog
+/// filters arrive already resolved to the product ids they allow.
+pub struct SearchFilters {
+    languages: Option<HashSet<String>>,
+    sentiment: Option<SentimentRange>,
+    labels: Option<HashSet<String>>,
+    category_products: Option<HashSet<String>>,
+    brand_products: Option<HashSet<String>>,
+}
+
+impl SearchFilters {
+    pub fn new(
+        filters: &ReviewFilters,
+        category_products: Option<HashSet<String>>,
+        brand_products: Option<HashSet<String>>,
+    ) -> Self {
+        Self {
+            languages: filters.languages.as_ref().map(|languages| {
+                languages.iter().filter_map(|language| normalize_language(language)).collect()
+            }),
+            sentiment: filters.sentiment,
+            labels: filters.labels.as_ref().map(|labels| labels.iter().cloned().collect()),
+            category_products,
+            brand_products,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.languages.is_some()
+            || self.sentiment.is_some()
+            || self.labels.is_some()
+            || self.category_products.is_some()
+            || self.brand_products.is_some()
+    }
+
+    pub fn matches(&self, review: &Review) -> bool {
+        self.decisions(review).iter().all(|decision| decision.passed)
+    }
+
+    /// One decision per active filter, with the review value it looked at.
+    pub fn decisions(&self, review: &Review) -> Vec<FilterDecision> {
+        let mut decisions = Vec::new();
+
+        if let Some(languages) = &self.languages {
+            decisions.push(FilterDecision {
+                filter: "languages".to_string(),
+                value: review.language.clone(),
+                passed: review.language.as_ref().is_some_and(|language| languages.contains(language)),
+            });
+        }
+
+        if let Some(range) = &self.sentiment {
+            decisions.push(FilterDecision {
+                filter: "sentiment".to_string(),
+                value: review.sentiment.map(|sentiment| format!("{:.3}", sentiment)),
+                passed: review.sentiment.is_some_and(|sentiment| range.contains(sentiment)),
+            });
+        }
+
+        if let Some(labels) = &self.labels {
+            decisions.push(FilterDecision {
+                filter: "labels".to_string(),
+                value: (!review.labels.is_empty()).then(|| review.labels.join(",")),
+                passed: review.labels.iter().any(|label| labels.contains(label)),
+            });
+        }
+
+        for (filter, products) in [("category", &self.category_products), ("brand", &self.brand_products)] {
+            if let Some(products) = products {
+                decisions.push(FilterDecision {
+                    filter: filter.to_string(),
+                    value: Some(review.product_id.clone()),
+                    passed: products.contains(&review.product_id),
+                });
+            }
+        }
+
+        decisions
+    }
+}
+
+/// Title and chunk similarities collected for one review during a search.
+#[derive(Default)]
+pub struct FieldHits {
+    pub title: Option<f32>,
+    pub chunks: Vec<ChunkMatch>,
+}
+
+impl FieldHits {
+    pub fn add_title(&mut self, score: f32) {
+        self.title = Some(self.title.map_or(score, |title| title.max(score)));
+    }
+
+    pub fn add_chunk(&mut self, span: ChunkSpan, score: f32) {
+        self.chunks.push(ChunkMatch {
+            start: span.start,
+            end: span.end,
+            score,
+        });
+    }
+}
+
+/// Normalized mean of a review's vectors, used to compare reviews with each
+/// other rather than with the query.
+pub fn mean_vector<'a>(vectors: impl Iterator<Item = &'a [f32]>) -> Vec<f32> {
+    let mut mean: Vec<f32> = Vec::new();
+    for vector in vectors {
+        if mean.is_empty() {
+            mean = vec![0.0; vector.len()];
+        }
+        for (sum, value) in mean.iter_mut().zip(vector) {
+            *sum += value;
+        }
+    }
+
+    let norm = mean.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        mean.iter_mut().for_each(|x| *x /= norm);
+    }
+
+    mean
+}
+
+/// Maximal marginal relevance: greedily picks up to `limit` items, each time
+/// taking the one with the best `(1 - diversity) * relevance - diversity *
+/// similarity to the closest item already picked`. With `diversity` 0 this
+/// is plain relevance order; towards 1 near-duplicates get pushed down.
+pub fn mmr_select<T>(candidates: Vec<(T, f32, Vec<f32>)>, diversity: f32, limit: usize) -> Vec<T> {
+    let mut remaining = candidates;
+    let mut picked_vectors: Vec<Vec<f32>> = Vec::new();
+    let mut picked = Vec::new();
+
+    while picked.len() < limit && !remaining.is_empty() {
+        let marginal = |(_, relevance, vector): &(T, f32, Vec<f32>)| {
+            let redundancy = picked_vectors
+                .iter()
+                .map(|other| cosine_similarity(vector, other))
+                .fold(f32::NEG_INFINITY, f32::max);
+            let redundancy = if redundancy.is_finite() { redundancy } else { 0.0 };
+            (1.0 - diversity) * relevance - diversity * redundancy
+        };
+
+        let best = remaining
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| marginal(a).total_cmp(&marginal(b)))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+
+        let (item, _, vector) = remaining.remove(best);
+        picked_vectors.push(vector);
+        picked.push(item);
+    }
+
+    picked
+}
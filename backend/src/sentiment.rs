@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Reviews at or past this sentiment in either direction count as positive
+/// or negative in stats; anything closer to 0 is neutral.
+pub const POLARITY_THRESHOLD: f32 = 0.25;
+
+/// Lexicon words flipped by a negation this many words before them.
+const NEGATION_WINDOW: usize = 3;
+
+const POSITIVE_WORDS: &[&str] = &[
+    "good", "great", "excellent", "amazing", "wonderful", "fantastic",
+    "love", "perfect", "best", "awesome", "outstanding", "superb",
+    "brilliant", "magnificent", "exceptional", "marvelous", "incredible",
+    "remarkable", "impressive", "stunning", "fabulous", "terrific",
+    "gorgeous", "beautiful", "lovely", "nice", "pleasant", "enjoyable",
+    "delightful", "charming", "attractive", "appealing", "desirable",
+    "superior", "premium", "top", "first-class", "high-quality", "fine",
+    "phenomenal", "friendly", "helpful", "patient", "knowledgeable",
+    "attentive", "cool", "affordable", "comfortable", "easy", "fast",
+    "durable", "portable", "compact", "smooth", "soft", "responsive",
+    "intuitive", "simple", "modern", "sleek", "elegant", "recommend",
+    "satisfied", "happy", "pleased", "worth", "enjoy", "appreciate",
+    "praise", "reliable", "stable", "clear", "bright", "quiet",
+    "quality", "value", "buy", "purchase",
+];
+
+const NEGATIVE_WORDS: &[&str] = &[
+    "bad", "poor", "terrible", "awful", "hate", "worst",
+    "horrible", "disappointing", "useless", "pathetic", "disgusting",
+    "dreadful", "appalling", "shocking", "disastrous", "catastrophic",
+    "horrendous", "atrocious", "abysmal", "deplorable", "miserable",
+    "inferior", "substandard", "defective", "faulty", "broken",
+    "worthless", "garbage", "trash", "junk", "rubbish", "cheap",
+    "flimsy", "fragile", "unreliable", "unstable", "problematic",
+    "suspicious", "difficult", "slow", "expensive", "heavy", "rough",
+    "hard", "laggy", "confusing", "complex", "outdated", "bulky",
+    "disappointed", "regret", "criticize", "complain", "blame",
+    "blurry", "dark", "loud", "uncomfortable", "return", "refund",
+];
+
+const NEGATIONS: &[&str] = &[
+    "not", "no", "never", "none", "nothing", "neither", "nor", "without", "hardly", "barely",
+    "don't", "doesn't", "didn't", "isn't", "wasn't", "aren't", "weren't", "won't", "wouldn't",
+    "can't", "cannot", "couldn't", "shouldn't", "haven't", "hasn't", "hadn't",
+];
+
+/// Word lists the analyzer scores with, as read from a lexicon file. Lists
+/// left out of the file keep their built-in defaults.
+#[derive(Debug, Default, Deserialize)]
+struct LexiconFile {
+    positive: Option<Vec<String>>,
+    negative: Option<Vec<String>>,
+    negations: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+struct Lexicon {
+    positive: HashSet<String>,
+    negative: HashSet<String>,
+    negations: HashSet<String>,
+}
+
+/// Lexicon-based review sentiment. Counts positive and negative words,
+/// flipping those shortly after a negation within the same clause, and
+/// scores `(positive - negative) / (positive + negative)`: 1 for purely
+/// positive wording, -1 for purely negative, 0 for none or a tie.
+#[derive(Debug, Clone)]
+pub struct SentimentAnalyzer {
+    lexicon: Arc<Lexicon>,
+}
+
+impl Default for SentimentAnalyzer {
+    fn default() -> Self {
+        Self::from_file(LexiconFile::default())
+    }
+}
+
+impl SentimentAnalyzer {
+    /// Loads a JSON lexicon of the form `{"positive": [...], "negative":
+    /// [...], "negations": [...]}`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read sentiment lexicon {}", path.display()))?;
+        let file: LexiconFile = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse sentiment lexicon {}", path.display()))?;
+        Ok(Self::from_file(file))
+    }
+
+    fn from_file(file: LexiconFile) -> Self {
+        let words = |custom: Option<Vec<String>>, default: &[&str]| -> HashSet<String> {
+            match custom {
+                Some(words) => words.iter().map(|word| normalize(word)).collect(),
+                None => default.iter().map(|word| word.to_string()).collect(),
+            }
+        };
+
+        Self {
+            lexicon: Arc::new(Lexicon {
+                positive: words(file.positive, POSITIVE_WORDS),
+                negative: words(file.negative, NEGATIVE_WORDS),
+                negations: words(file.negations, NEGATIONS),
+            }),
+        }
+    }
+
+    pub fn score(&self, text: &str) -> f32 {
+        let text = normalize(text);
+        let mut positive = 0.0f32;
+        let mut negative = 0.0f32;
+
+        for clause in text.split(['.', ',', ';', ':', '!', '?', '\n']) {
+            let mut since_negation = None;
+            for word in clause
+                .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+                .map(|word| word.trim_matches(|c| c == '\'' || c == '-'))
+                .filter(|word| !word.is_empty())
+            {
+                if self.lexicon.negations.contains(word) {
+                    since_negation = Some(0);
+                    continue;
+                }
+
+                let negated = since_negation.is_some_and(|distance| distance < NEGATION_WINDOW);
+                since_negation = since_negation.map(|distance| distance + 1);
+
+                let is_positive = if self.lexicon.positive.contains(word) {
+                    true
+                } else if self.lexicon.negative.contains(word) {
+                    false
+                } else {
+                    continue;
+                };
+
+                if is_positive != negated {
+                    positive += 1.0;
+                } else {
+                    negative += 1.0;
+                }
+            }
+        }
+
+        if positive + negative > 0.0 {
+            (positive - negative) / (positive + negative)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Lowercases and straightens curly apostrophes, so "Don’t" matches "don't".
+fn normalize(text: &str) -> String {
+    text.to_lowercase().replace(['\u{2019}', '\u{2018}'], "'")
+}
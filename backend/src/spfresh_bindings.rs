@@ -1,7 +1,11 @@
 use std::os::raw::{c_char, c_int, c_float};
 use std::ffi::{CString, CStr};
+use std::path::Path;
 use std::ptr;
 
+/// File the C++ wrapper keeps its vectors in, inside the index directory.
+pub const INDEX_FILE: &str = "reviews.index";
+
 #[repr(C)]
 pub struct SPFreshIndex {
     _private: [u8; 0],
@@ -9,7 +13,7 @@ pub struct SPFreshIndex {
 
 #[link(name = "spfresh_c_api")]
 extern "C" {
-    pub fn spfresh_create_index(dimension: c_int) -> *mut SPFreshIndex;
+    pub fn spfresh_create_index_at(dimension: c_int, directory: *const c_char) -> *mut SPFreshIndex;
     pub fn spfresh_destroy_index(index: *mut SPFreshIndex);
     pub fn spfresh_add_vector(index: *mut SPFreshIndex, data: *const c_float, metadata: *const c_char) -> c_int;
     pub fn spfresh_search(index: *mut SPFreshIndex, query: *const c_float, k: c_int, result_ids: *mut c_int, result_distances: *mut c_float) -> c_int;
@@ -22,17 +26,35 @@ pub struct SPFreshIndexWrapper {
 }
 
 impl SPFreshIndexWrapper {
-    pub fn new(dimension: usize) -> Result<Self, String> {
-        let index = unsafe { spfresh_create_index(dimension as c_int) };
-        
+    /// Creates an empty index in `directory`, creating the directory if
+    /// needed and discarding any index file already there.
+    pub fn empty_at(dimension: usize, directory: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(directory)
+            .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        let file = directory.join(INDEX_FILE);
+        if file.exists() {
+            std::fs::remove_file(&file).map_err(|e| format!("Failed to remove {}: {}", file.display(), e))?;
+        }
+
+        Self::with_data_dir(dimension, &directory.to_string_lossy())
+    }
+
+    /// Opens the index that keeps its files in `directory`, loading the
+    /// vectors already saved there.
+    pub fn with_data_dir(dimension: usize, directory: &str) -> Result<Self, String> {
+        let directory = CString::new(directory).map_err(|e| format!("Invalid index directory: {}", e))?;
+        let index = unsafe { spfresh_create_index_at(dimension as c_int, directory.as_ptr()) };
+
         if index.is_null() {
             Err("Failed to create SPFresh index".to_string())
         } else {
             Ok(Self { inner: index, dimension })
         }
     }
-    
-    pub fn add_vector(&self, vector: &[f32], metadata: Option<&str>) -> Result<i32, String> {
+
+    /// Appends a vector. The C++ store does no locking of its own, so this
+    /// takes `&mut self`: adds can't overlap each other or a search.
+    pub fn add_vector(&mut self, vector: &[f32], metadata: Option<&str>) -> Result<i32, String> {
         if vector.len() != self.dimension {
             return Err(format!("Vector dimension mismatch: expected {}, got {}", self.dimension, vector.len()));
         }
@@ -94,5 +116,7 @@ impl Drop for SPFreshIndexWrapper {
     }
 }
 
+// Searches and counts only read the C++ store, so they may run from several
+// threads at once; `add_vector` needs exclusive access.
 unsafe impl Send for SPFreshIndexWrapper {}
 unsafe impl Sync for SPFreshIndexWrapper {}
@@ -1,31 +1,155 @@
 use crate::spfresh_bindings::SPFreshIndexWrapper;
-use crate::models::{Review, InsertReviewRequest};
+use crate::alerts::{AlertStore, DeliveryConfig, SavedAlert};
+use crate::aspects::{self, split_clauses, AspectConfig, Mention};
+use crate::models::{
+    Alert, AlertMatch, ChunkAggregation, ClusterRequest, ClusterRun, CreateAlertRequest, CreateLabelRequest, Label, LabelBackfillReport, ExplainResponse, FieldWeights, ExplainedHit, FieldScores, HitSource, RejectedCandidate, Review,
+    FacetBucket, InsertReviewRequest, CreateProductRequest, Product, ProductAspects, ProductFields, ProductImportReport, ProductSummary, RepresentativeReview, SimilarProduct, DuplicateCluster, ModerationDecision, ModerationDecisionRequest, ModerationItem, ModerationStatus, DuplicateMember, DuplicateReport, ReviewFilters, RatingSentiment, ReviewField, ReviewWithScore, SearchRequest, SearchResponse, SentimentStats, StatsResponse,
+    VectorScore,
+};
+use crate::catalog::ProductCatalog;
+use crate::chunking::{chunk_body, ChunkConfig, ChunkSpan};
+use crate::clustering::{self, ClusterScope, ClusterStore};
+use crate::duplicates::{self, DuplicateConfig, DuplicateMode, DuplicateReview};
+use crate::embedder::Embedder;
+use crate::events::{EventBus, EventConfig, EventKind, Subscription};
 use crate::fastembed_service::FastEmbedService;
+use crate::labels::{assign_labels, LabelStore, SavedLabel};
+use crate::language::detect_language;
+use crate::moderation::{self, normalize_body, ModerationStore};
+use crate::product_index::ProductIndex;
+use crate::ranking::RankingConfig;
+use crate::redaction::{PiiRejected, RedactionConfig, RedactionMode};
+use crate::sentiment::{SentimentAnalyzer, POLARITY_THRESHOLD};
+use crate::search::{build_response, facet_counts, grouped_candidates, mean_vector, mmr_select, FieldHits, SearchFilters};
+use crate::vector_store::cosine_similarity;
 use anyhow::Result;
 use serde_json;
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use uuid::Uuid;
 
-pub struct SPFreshVectorStore {
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Directory of the serving index files inside the data directory.
+const INDEX_DIR: &str = "index";
+
+/// Where `rebuild_with` builds the new index while the serving one is in use.
+const STAGING_INDEX_DIR: &str = "index.staging";
+
+/// Where `promote` moves the replaced index until the new one is in place.
+const RETIRED_INDEX_DIR: &str = "index.retired";
+
+/// Vectors fetched from the index per requested result, so reviews matching
+/// in their title and several chunks still fill the page.
+const CANDIDATES_PER_RESULT: usize = 4;
+
+/// Similarity a review vector needs to a prototype when a label doesn't set
+/// its own threshold.
+const DEFAULT_LABEL_THRESHOLD: f32 = 0.6;
+
+/// Index vectors fetched when looking for near-duplicates of a review.
+const DUPLICATE_CANDIDATES: usize = 20;
+
+/// Ranked reviews handed to diversity re-ranking per requested result.
+const DIVERSITY_POOL_PER_RESULT: usize = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct StoreConfig {
+    pub chunking: ChunkConfig,
+    pub ranking: RankingConfig,
+    pub webhooks: DeliveryConfig,
+    pub events: EventConfig,
+    pub sentiment: SentimentAnalyzer,
+    pub aspects: AspectConfig,
+    pub duplicates: DuplicateConfig,
+    /// Queue new reviews for a moderator instead of publishing them.
+    pub moderation: bool,
+    pub redaction: RedactionConfig,
+}
+
+/// Which part of a review an index vector was embedded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VectorSource {
+    Title,
+    Body(ChunkSpan),
+}
+
+/// An index vector's review and source. The vector itself is kept so scores
+/// for fields the index didn't return can be computed exactly.
+#[derive(Debug, Clone)]
+struct VectorEntry {
+    review_index: usize,
+    source: VectorSource,
+    vector: Vec<f32>,
+}
+
+/// State that outlives a reindex, shared by a store and the stores rebuilt
+/// from it: alert and label definitions (re-embedded when the model
+/// changes), cluster assignments by review id, the product catalog, the
+/// moderation queue, and the event bus so `/events` subscribers stay
+/// connected across the switch.
+#[derive(Clone)]
+struct Shared {
+    alerts: Arc<AlertStore>,
+    events: Arc<EventBus>,
+    clusters: Arc<ClusterStore>,
+    labels: Arc<LabelStore>,
+    catalog: Arc<ProductCatalog>,
+    moderation: Arc<ModerationStore>,
+}
+
+pub struct SPFreshVectorStore<E: Embedder = FastEmbedService> {
     index: Arc<RwLock<Option<SPFreshIndexWrapper>>>,
+    product_index: Arc<ProductIndex>,
     reviews: Arc<RwLock<Vec<Review>>>,
-    id_to_review_index: Arc<RwLock<HashMap<i32, usize>>>,
-    fastembed_service: FastEmbedService,
+    vector_entries: Arc<RwLock<HashMap<i32, VectorEntry>>>,
+    review_vectors: Arc<RwLock<HashMap<String, Vec<i32>>>>,
+    alerts: Arc<AlertStore>,
+    events: Arc<EventBus>,
+    clusters: Arc<ClusterStore>,
+    labels: Arc<LabelStore>,
+    catalog: Arc<ProductCatalog>,
+    moderation: Arc<ModerationStore>,
+    embedder: E,
+    config: StoreConfig,
     data_dir: String,
+    /// Directory holding this store's review and product index files.
+    index_dir: PathBuf,
     dimension: usize,
     next_id: Arc<RwLock<i32>>,
 }
 
-impl SPFreshVectorStore {
-    pub async fn new(data_dir: &str, fastembed_service: FastEmbedService) -> Result<Self> {
-        // Create SPFresh index with FastEmbed dimension (384)
-        let dimension = 384;
-        let index = match SPFreshIndexWrapper::new(dimension) {
+impl<E: Embedder> SPFreshVectorStore<E> {
+    pub async fn new(data_dir: &str, embedder: E, config: StoreConfig) -> Result<Self> {
+        let shared = Shared {
+            alerts: Arc::new(AlertStore::load(data_dir, config.webhooks)?),
+            events: Arc::new(EventBus::new(config.events)),
+            clusters: Arc::new(ClusterStore::load(data_dir)?),
+            labels: Arc::new(LabelStore::load(data_dir)?),
+            catalog: Arc::new(ProductCatalog::load(data_dir)?),
+            moderation: Arc::new(ModerationStore::load(data_dir)?),
+        };
+        let store = Self::empty(data_dir, Path::new(data_dir).join(INDEX_DIR), embedder, config, shared);
+
+        // Load existing data if any
+        store.load_existing_data().await?;
+        store.refresh_alert_embeddings().await?;
+        store.refresh_label_embeddings().await?;
+        
+        Ok(store)
+    }
+
+    /// A store without reviews whose indexes start out empty in `index_dir`.
+    /// Reviews are re-embedded on load, so index files left by an earlier run
+    /// are discarded rather than loaded and then added to again.
+    fn empty(data_dir: &str, index_dir: PathBuf, embedder: E, config: StoreConfig, shared: Shared) -> Self {
+        // Create SPFresh index with the dimension of the loaded model
+        let dimension = embedder.dimension();
+        let index = match SPFreshIndexWrapper::empty_at(dimension, &index_dir) {
             Ok(idx) => Some(idx),
             Err(e) => {
                 eprintln!("Failed to create SPFresh index: {}", e);
@@ -33,20 +157,25 @@ impl SPFreshVectorStore {
             }
         };
 
-        let store = Self {
+        Self {
             index: Arc::new(RwLock::new(index)),
+            product_index: Arc::new(ProductIndex::new(&index_dir, dimension)),
             reviews: Arc::new(RwLock::new(Vec::new())),
-            id_to_review_index: Arc::new(RwLock::new(HashMap::new())),
-            fastembed_service,
+            vector_entries: Arc::new(RwLock::new(HashMap::new())),
+            review_vectors: Arc::new(RwLock::new(HashMap::new())),
+            alerts: shared.alerts,
+            events: shared.events,
+            clusters: shared.clusters,
+            labels: shared.labels,
+            catalog: shared.catalog,
+            moderation: shared.moderation,
+            embedder,
+            config,
             data_dir: data_dir.to_string(),
+            index_dir,
             dimension,
             next_id: Arc::new(RwLock::new(0)),
-        };
-
-        // Load existing data if any
-        store.load_existing_data().await?;
-        
-        Ok(store)
+        }
     }
 
     async fn load_existing_data(&self) -> Result<()> {
@@ -59,89 +188,689 @@ impl SPFreshVectorStore {
         let file = File::open(&metadata_path)?;
         let reader = BufReader::new(file);
 
-        let mut reviews = self.reviews.write().await;
-        let mut id_to_review_index = self.id_to_review_index.write().await;
-        let mut next_id = self.next_id.write().await;
-
         for line in reader.lines() {
             let line = line?;
             if !line.trim().is_empty() {
                 let review: Review = serde_json::from_str(&line)?;
-                
-                // Generate embedding for the review
-                let text_to_embed = format!("{} {}", review.review_title, review.review_body);
-                let embedding = self.fastembed_service.embed_text(&text_to_embed)?;
-
-                // Add to SPFresh index
-                if let Some(ref index) = *self.index.read().await {
-                    let vector_id = match index.add_vector(&embedding, Some(&review.id)) {
-                        Ok(id) => id,
-                        Err(e) => {
-                            eprintln!("Failed to add vector to SPFresh index: {}", e);
-                            continue;
-                        }
-                    };
-
-                    let review_index = reviews.len();
-                    id_to_review_index.insert(vector_id, review_index);
-                    reviews.push(review);
-                    
-                    if vector_id >= *next_id {
-                        *next_id = vector_id + 1;
-                    }
-                }
+                self.index_existing_review(review).await?;
             }
         }
+        self.product_index.compact().await?;
+
+        println!("Loaded {} existing reviews into SPFresh index", self.reviews.read().await.len());
+        Ok(())
+    }
+
+    /// Embeds an already persisted review and adds it to the index. Reviews
+    /// the index rejects are skipped so one bad vector doesn't block startup.
+    async fn index_existing_review(&self, mut review: Review) -> Result<()> {
+        // Reviews stored before language detection and sentiment scoring
+        // existed get them on load
+        let text = format!("{} {}", review.review_title, review.review_body);
+        if review.language.is_none() {
+            review.language = detect_language(&text);
+        }
+        if review.sentiment.is_none() {
+            review.sentiment = Some(self.config.sentiment.score(&text));
+        }
+
+        let embedded = self.embed_review(&review).await?;
+
+        // Add to SPFresh index
+        if let Some(index) = self.index.write().await.as_mut() {
+            let vector_ids = match Self::add_vectors(index, &review, &embedded) {
+                Ok(ids) => ids,
+                Err(e) => {
+                    eprintln!("Failed to add vector to SPFresh index: {}", e);
+                    return Ok(());
+                }
+            };
+            let review_vector = mean_vector(vector_ids.iter().map(|(_, _, vector)| vector.as_slice()));
+
+            let mut reviews = self.reviews.write().await;
+            self.map_vectors(reviews.len(), &review.id, vector_ids).await;
+            self.add_to_product(&review.product_id, &review_vector).await;
+            reviews.push(review);
+        }
 
-        println!("Loaded {} existing reviews into SPFresh index", reviews.len());
         Ok(())
     }
 
+    /// Embeds the title and each body chunk separately, so a short emphatic
+    /// title doesn't drown out what the body says. Blank titles get no vector.
+    async fn embed_review(&self, review: &Review) -> Result<Vec<(VectorSource, Vec<f32>)>> {
+        let mut sources = Vec::new();
+        let mut texts = Vec::new();
+
+        if !review.review_title.trim().is_empty() {
+            sources.push(VectorSource::Title);
+            texts.push(review.review_title.as_str());
+        }
+        for span in chunk_body(&review.review_body, &self.config.chunking) {
+            sources.push(VectorSource::Body(span));
+            texts.push(&review.review_body[span.start..span.end]);
+        }
+
+        let embeddings = self.embedder.embed_batch(texts).await?;
+
+        Ok(sources.into_iter().zip(embeddings).collect())
+    }
+
+    fn add_vectors(
+        index: &mut SPFreshIndexWrapper,
+        review: &Review,
+        embedded: &[(VectorSource, Vec<f32>)],
+    ) -> Result<Vec<(i32, VectorSource, Vec<f32>)>> {
+        embedded
+            .iter()
+            .map(|(source, embedding)| {
+                let vector_id = index
+                    .add_vector(embedding, Some(&review.id))
+                    .map_err(|e| anyhow::anyhow!("Failed to add vector to SPFresh index: {}", e))?;
+                Ok((vector_id, *source, embedding.clone()))
+            })
+            .collect()
+    }
+
+    async fn map_vectors(
+        &self,
+        review_index: usize,
+        review_id: &str,
+        vector_ids: Vec<(i32, VectorSource, Vec<f32>)>,
+    ) {
+        let mut vector_entries = self.vector_entries.write().await;
+        let mut review_vectors = self.review_vectors.write().await;
+        let mut next_id = self.next_id.write().await;
+
+        for (vector_id, source, vector) in vector_ids {
+            vector_entries.insert(vector_id, VectorEntry { review_index, source, vector });
+            review_vectors.entry(review_id.to_string()).or_default().push(vector_id);
+            if vector_id >= *next_id {
+                *next_id = vector_id + 1;
+            }
+        }
+    }
+
+    /// Folds a review's mean vector into its product's embedding. A failure
+    /// only costs the product an up-to-date embedding, so it is logged.
+    async fn add_to_product(&self, product_id: &str, review_vector: &[f32]) {
+        if let Err(e) = self.product_index.add_review(product_id, review_vector).await {
+            tracing::warn!("Failed to update embedding of product {}: {}", product_id, e);
+        }
+    }
+
+    /// Builds a new store over the same data directory with another embedding
+    /// model while `self` keeps serving. Its indexes are built in a staging
+    /// directory, so the serving index files are left alone. Call `catch_up`
+    /// and then `promote` to swap it in.
+    pub async fn rebuild_with(&self, embedder: E) -> Result<Self> {
+        let shared = Shared {
+            alerts: Arc::clone(&self.alerts),
+            events: Arc::clone(&self.events),
+            clusters: Arc::clone(&self.clusters),
+            labels: Arc::clone(&self.labels),
+            catalog: Arc::clone(&self.catalog),
+            moderation: Arc::clone(&self.moderation),
+        };
+        let staging = Path::new(&self.data_dir).join(STAGING_INDEX_DIR);
+        if staging.exists() {
+            // Left over from a reindex that didn't finish
+            std::fs::remove_dir_all(&staging)?;
+        }
+        let rebuilt = Self::empty(&self.data_dir, staging, embedder, self.config.clone(), shared);
+        let snapshot = self.reviews.read().await.clone();
+
+        for review in snapshot {
+            rebuilt.index_existing_review(review).await?;
+        }
+
+        Ok(rebuilt)
+    }
+
+    /// Applies inserts and deletes that reached `old` while `rebuild_with` ran,
+    /// and takes `old`'s copy of every other review so changes made to stored
+    /// reviews meanwhile, like label backfills, aren't lost in the swap.
+    pub async fn catch_up(&self, old: &Self) -> Result<()> {
+        let current = old.reviews.read().await.clone();
+        let current_ids: HashSet<&str> = current.iter().map(|review| review.id.as_str()).collect();
+
+        let (known_ids, stale_ids): (HashSet<String>, Vec<String>) = {
+            let reviews = self.reviews.read().await;
+            let known = reviews.iter().map(|review| review.id.clone()).collect();
+            let stale = reviews
+                .iter()
+                .filter(|review| !current_ids.contains(review.id.as_str()))
+                .map(|review| review.id.clone())
+                .collect();
+            (known, stale)
+        };
+
+        for review in current.iter().filter(|review| !known_ids.contains(&review.id)) {
+            self.index_existing_review(review.clone()).await?;
+        }
+
+        for review_id in stale_ids {
+            self.forget_review(&review_id).await;
+        }
+
+        // Vectors map to review positions, not contents, so replacing the
+        // records in place keeps them searchable
+        let current_by_id: HashMap<&str, &Review> = current.iter().map(|review| (review.id.as_str(), review)).collect();
+        for review in self.reviews.write().await.iter_mut() {
+            if let Some(current) = current_by_id.get(review.id.as_str()) {
+                *review = (*current).clone();
+            }
+        }
+
+        self.refresh_alert_embeddings().await?;
+        self.refresh_label_embeddings().await
+    }
+
+    /// Moves the indexes of a store built by `rebuild_with` into the serving
+    /// index directory of `old`, which is closed and can't serve afterwards.
+    /// The index files are written when their handles are dropped, so both
+    /// stores' indexes are closed before the staging directory is renamed
+    /// over the serving one. Neither store may handle requests meanwhile. If
+    /// the rename fails, `old` is reopened and keeps serving.
+    pub async fn promote(&mut self, old: &Self) -> Result<()> {
+        old.close_indexes().await;
+        self.close_indexes().await;
+
+        if let Err(e) = replace_dir(&self.index_dir, &old.index_dir, &Path::new(&self.data_dir).join(RETIRED_INDEX_DIR)) {
+            old.reopen_indexes(&old.index_dir).await?;
+            self.reopen_indexes(&self.index_dir).await?;
+            return Err(e);
+        }
+
+        self.index_dir = old.index_dir.clone();
+        self.reopen_indexes(&self.index_dir).await
+    }
+
+    async fn close_indexes(&self) {
+        *self.index.write().await = None;
+        self.product_index.close().await;
+    }
+
+    /// Opens the indexes saved in `index_dir` after `close_indexes`.
+    async fn reopen_indexes(&self, index_dir: &Path) -> Result<()> {
+        let index = SPFreshIndexWrapper::with_data_dir(self.dimension, &index_dir.to_string_lossy())
+            .map_err(|e| anyhow::anyhow!("Failed to open SPFresh index: {}", e))?;
+        *self.index.write().await = Some(index);
+        self.product_index.reopen(index_dir).await
+    }
+
+    /// See `EventBus::subscribe`.
+    pub fn subscribe(&self, last_event_id: Option<&str>) -> Subscription {
+        self.events.subscribe(last_event_id)
+    }
+
+    /// See `EventBus::event_id`.
+    pub fn event_id(&self, seq: u64) -> String {
+        self.events.event_id(seq)
+    }
+
+    pub fn model_id(&self) -> &str {
+        self.embedder.model_id()
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Creates a review from the request, with personal data redacted before
+    /// anything is embedded or stored. In moderation mode it is queued as
+    /// pending, with its pre-flags, until a moderator approves it; otherwise
+    /// it is published right away.
     pub async fn insert_review(&self, request: InsertReviewRequest) -> Result<Review> {
+        let redaction = &self.config.redaction;
+        let mut redactions = Vec::new();
+        let review_title = redaction.redact(&request.review_title, &mut redactions);
+        let review_body = redaction.redact(&request.review_body, &mut redactions);
+        if redaction.mode == RedactionMode::Reject && !redactions.is_empty() {
+            return Err(PiiRejected { detectors: redactions }.into());
+        }
+
         // Create review with unique ID and timestamp
-        let review = Review {
+        let text = format!("{} {}", review_title, review_body);
+        let mut review = Review {
             id: Uuid::new_v4().to_string(),
-            review_title: request.review_title.clone(),
-            review_body: request.review_body.clone(),
+            review_title,
+            review_body,
             product_id: request.product_id,
             review_rating: request.review_rating,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            language: detect_language(&text),
+            sentiment: Some(self.config.sentiment.score(&text)),
+            labels: Vec::new(),
+            duplicate_of: None,
+            redactions,
+            status: None,
         };
 
-        // Generate FastEmbed embedding
-        let text_to_embed = format!("{} {}", request.review_title, request.review_body);
-        let embedding = self.fastembed_service.embed_text(&text_to_embed)?;
+        if self.config.moderation {
+            review.status = Some(ModerationStatus::Pending);
+            let body = normalize_body(&review.review_body);
+            let duplicate_body = !body.is_empty()
+                && (self
+                    .reviews
+                    .read()
+                    .await
+                    .iter()
+                    .any(|stored| normalize_body(&stored.review_body) == body)
+                    || self.moderation.has_body(&body).await);
+            let flags = moderation::pre_flags(&review, duplicate_body);
+            self.moderation.enqueue(ModerationItem { review: review.clone(), flags }).await?;
+            return Ok(review);
+        }
 
-        // Add to SPFresh index
-        let vector_id = if let Some(ref index) = *self.index.read().await {
-            match index.add_vector(&embedding, Some(&review.id)) {
-                Ok(id) => id,
+        self.publish(review).await
+    }
+
+    /// Embeds, labels, indexes and persists a review, then tells saved
+    /// alerts and event subscribers about it.
+    async fn publish(&self, mut review: Review) -> Result<Review> {
+        // Generate FastEmbed embeddings for the title and each body chunk
+        let embedded = self.embed_review(&review).await?;
+        let vectors: Vec<&[f32]> = embedded.iter().map(|(_, vector)| vector.as_slice()).collect();
+        review.labels = assign_labels(&self.current_labels().await, &vectors);
+        let review_vector = mean_vector(vectors.into_iter());
+
+        {
+            // The index write lock is held from the duplicate check until the
+            // review is stored, so two identical inserts can't both pass the
+            // check; it also keeps searches out while vectors are added
+            let mut index_guard = self.index.write().await;
+            let Some(index) = index_guard.as_mut() else {
+                return Err(anyhow::anyhow!("SPFresh index not initialized"));
+            };
+
+            let duplicates = self.config.duplicates;
+            if duplicates.mode != DuplicateMode::Off {
+                let duplicate = self.nearest_duplicate(index, &review_vector, duplicates.threshold).await?;
+                if let Some((duplicate_of, similarity)) = duplicate {
+                    if duplicates.mode == DuplicateMode::Reject {
+                        return Err(DuplicateReview { duplicate_of, similarity }.into());
+                    }
+                    review.duplicate_of = Some(duplicate_of);
+                }
+            }
+
+            let vector_ids = Self::add_vectors(index, &review, &embedded)?;
+
+            // Add to in-memory storage
+            let mut reviews = self.reviews.write().await;
+            self.map_vectors(reviews.len(), &review.id, vector_ids).await;
+            self.add_to_product(&review.product_id, &review_vector).await;
+            reviews.push(review.clone());
+
+            // Append to file storage
+            self.append_to_storage(&review).await?;
+        }
+
+        let alert_matches = self.percolate(&review, &embedded).await;
+        self.events.publish(EventKind::ReviewInserted, &review);
+        self.notify_alerts(alert_matches);
+
+        Ok(review)
+    }
+
+    /// Reviews waiting for a moderator, oldest first.
+    pub async fn moderation_queue(&self) -> Vec<ModerationItem> {
+        self.moderation.queue().await
+    }
+
+    /// Approves (publishes) or rejects (drops) a queued review and logs the
+    /// decision. `None` if the review isn't in the queue. A review whose
+    /// publication fails stays queued; a decision that can't be logged still
+    /// stands.
+    pub async fn moderate(
+        &self,
+        review_id: &str,
+        status: ModerationStatus,
+        request: ModerationDecisionRequest,
+    ) -> Result<Option<ModerationDecision>> {
+        let Some(item) = self.moderation.take(review_id).await? else {
+            return Ok(None);
+        };
+
+        let mut review = item.review.clone();
+        review.status = Some(status);
+        if status == ModerationStatus::Approved {
+            review = match self.publish(review).await {
+                Ok(review) => review,
                 Err(e) => {
-                    return Err(anyhow::anyhow!("Failed to add vector to SPFresh index: {}", e));
+                    self.moderation.restore(item).await?;
+                    return Err(e);
+                }
+            };
+        }
+
+        let decision = ModerationDecision {
+            review,
+            status,
+            moderator: request.moderator,
+            note: request.note,
+            flags: item.flags,
+            decided_at: chrono::Utc::now().to_rfc3339(),
+        };
+        // The decision has taken effect by now, so a failed audit write is
+        // logged rather than reported as a failed decision
+        if let Err(e) = self.moderation.record_decision(&decision) {
+            tracing::warn!("Failed to log moderation decision for review {}: {}", review_id, e);
+        }
+
+        Ok(Some(decision))
+    }
+
+    /// The stored review most similar to `review_vector` by mean vector, if
+    /// at least `threshold` similar. Candidates come from the index, so a
+    /// duplicate is found as long as one of its vectors ranks near the top.
+    async fn nearest_duplicate(
+        &self,
+        index: &SPFreshIndexWrapper,
+        review_vector: &[f32],
+        threshold: f32,
+    ) -> Result<Option<(String, f32)>> {
+        let reviews = self.reviews.read().await;
+        let vector_entries = self.vector_entries.read().await;
+        let review_vectors = self.review_vectors.read().await;
+
+        let found = near_duplicates(index, &reviews, &vector_entries, &review_vectors, review_vector, None, threshold)?;
+        Ok(found
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(review_index, similarity)| (reviews[review_index].id.clone(), similarity)))
+    }
+
+    /// Clusters of near-duplicate reviews across the corpus: every review is
+    /// looked up in the index like a new one, and reviews linked by pairs at
+    /// least `threshold` similar end up in the same cluster.
+    pub async fn duplicate_report(&self, threshold: Option<f32>) -> Result<DuplicateReport> {
+        let threshold = threshold.unwrap_or(self.config.duplicates.threshold);
+
+        let index_guard = self.index.read().await;
+        let reviews = self.reviews.read().await;
+        let vector_entries = self.vector_entries.read().await;
+        let review_vectors = self.review_vectors.read().await;
+
+        let mut pairs = Vec::new();
+        let mut similarities: HashMap<(usize, usize), f32> = HashMap::new();
+        if let Some(ref index) = *index_guard {
+            for (review_index, review) in reviews.iter().enumerate() {
+                let review_vector = stored_review_vector(&review.id, &vector_entries, &review_vectors);
+                if review_vector.is_empty() {
+                    continue;
+                }
+                let found = near_duplicates(
+                    index,
+                    &reviews,
+                    &vector_entries,
+                    &review_vectors,
+                    &review_vector,
+                    Some(review_index),
+                    threshold,
+                )?;
+                for (other, similarity) in found {
+                    let pair = (review_index.min(other), review_index.max(other));
+                    if similarities.insert(pair, similarity).is_none() {
+                        pairs.push(pair);
+                    }
                 }
             }
-        } else {
-            return Err(anyhow::anyhow!("SPFresh index not initialized"));
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = duplicates::group_pairs(&pairs)
+            .into_iter()
+            .map(|members| {
+                let min_similarity = similarities
+                    .iter()
+                    .filter(|((a, _), _)| members.binary_search(a).is_ok())
+                    .map(|(_, similarity)| *similarity)
+                    .fold(f32::INFINITY, f32::min);
+                let mut members: Vec<&Review> = members.iter().map(|&i| &reviews[i]).collect();
+                members.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+                let products: HashSet<&str> = members.iter().map(|review| review.product_id.as_str()).collect();
+
+                DuplicateCluster {
+                    products: products.len(),
+                    min_similarity,
+                    reviews: members
+                        .into_iter()
+                        .map(|review| DuplicateMember {
+                            review_id: review.id.clone(),
+                            product_id: review.product_id.clone(),
+                            review_title: review.review_title.clone(),
+                            timestamp: review.timestamp.clone(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.reviews.len()));
+
+        Ok(DuplicateReport {
+            threshold,
+            duplicate_reviews: clusters.iter().map(|cluster| cluster.reviews.len()).sum(),
+            clusters,
+        })
+    }
+
+    /// Matches a new review's vectors against every saved alert, the reverse
+    /// of a search. Alerts are scored like search hits with the default field
+    /// weights and chunk aggregation, so they compare against the same score
+    /// `/search` reports.
+    async fn percolate(&self, review: &Review, embedded: &[(VectorSource, Vec<f32>)]) -> Vec<(AlertMatch, Option<String>)> {
+        let mut matches = Vec::new();
+
+        for saved in self.alerts.saved().await {
+            if !self.search_filters(&saved.alert.filters).await.matches(review) {
+                continue;
+            }
+
+            // Alerts saved under another model until refreshed after a reindex
+            let query_vector = if saved.model == self.model_id() {
+                saved.embedding
+            } else {
+                match self.embedder.embed_query(&saved.alert.query).await {
+                    Ok(embedding) => embedding,
+                    Err(e) => {
+                        tracing::warn!("Failed to embed alert {}: {}", saved.alert.id, e);
+                        continue;
+                    }
+                }
+            };
+
+            let mut found = FieldHits::default();
+            for (source, vector) in embedded {
+                let score = cosine_similarity(&query_vector, vector);
+                match *source {
+                    VectorSource::Title => found.add_title(score),
+                    VectorSource::Body(span) => found.add_chunk(span, score),
+                }
+            }
+            let mut chunk_scores: Vec<f32> = found.chunks.iter().map(|chunk| chunk.score).collect();
+            chunk_scores.sort_by(|a, b| b.total_cmp(a));
+            let body = ChunkAggregation::default().combine(&chunk_scores);
+            let score = FieldWeights::default().fuse(found.title.unwrap_or(0.0), body);
+
+            if score >= saved.alert.threshold {
+                let alert_match = AlertMatch {
+                    id: Uuid::new_v4().to_string(),
+                    alert_id: saved.alert.id.clone(),
+                    query: saved.alert.query.clone(),
+                    score,
+                    review: review.clone(),
+                    matched_at: chrono::Utc::now().to_rfc3339(),
+                };
+                matches.push((alert_match, saved.alert.webhook_url));
+            }
+        }
+
+        matches
+    }
+
+    /// Records matches under `alerts/` and hands them to their webhooks.
+    fn notify_alerts(&self, matches: Vec<(AlertMatch, Option<String>)>) {
+        for (alert_match, webhook_url) in matches {
+            if let Err(e) = self.alerts.record_match(&alert_match) {
+                tracing::warn!("Failed to record match for alert {}: {}", alert_match.alert_id, e);
+            }
+            self.events.publish(EventKind::AlertMatched, &alert_match);
+            if let Some(url) = webhook_url {
+                self.alerts.deliver(url, alert_match);
+            }
+        }
+    }
+
+    pub async fn create_alert(&self, request: CreateAlertRequest) -> Result<Alert> {
+        let embedding = self.embedder.embed_query(&request.query).await?;
+
+        let alert = Alert {
+            id: Uuid::new_v4().to_string(),
+            query: request.query,
+            threshold: request.threshold,
+            filters: request.filters,
+            webhook_url: request.webhook_url,
+            created_at: chrono::Utc::now().to_rfc3339(),
         };
+        self.alerts
+            .add(SavedAlert {
+                alert: alert.clone(),
+                model: self.model_id().to_string(),
+                embedding,
+            })
+            .await?;
+
+        Ok(alert)
+    }
+
+    pub async fn list_alerts(&self) -> Vec<Alert> {
+        self.alerts.list().await
+    }
+
+    pub async fn delete_alert(&self, alert_id: &str) -> Result<Option<Alert>> {
+        self.alerts.remove(alert_id).await
+    }
+
+    /// Recorded matches of an alert, or `None` if there is no such alert.
+    pub async fn alert_matches(&self, alert_id: &str) -> Result<Option<Vec<AlertMatch>>> {
+        if !self.alerts.list().await.iter().any(|alert| alert.id == alert_id) {
+            return Ok(None);
+        }
+
+        self.alerts.matches(alert_id).map(Some)
+    }
+
+    /// Re-embeds alert queries saved under a different model.
+    async fn refresh_alert_embeddings(&self) -> Result<()> {
+        let mut embeddings = HashMap::new();
+        for saved in self.alerts.saved().await {
+            if saved.model != self.model_id() {
+                let embedding = self.embedder.embed_query(&saved.alert.query).await?;
+                embeddings.insert(saved.alert.id, embedding);
+            }
+        }
+
+        self.alerts.replace_embeddings(self.model_id(), embeddings).await
+    }
+
+    /// Adds a label, or redefines the one with the same name. Reviews already
+    /// stored keep their labels until `backfill_labels` runs.
+    pub async fn upsert_label(&self, request: CreateLabelRequest) -> Result<Label> {
+        let prototypes: Vec<&str> = request.prototypes.iter().map(String::as_str).collect();
+        let embeddings = self.embedder.embed_batch(prototypes).await?;
+
+        let label = Label {
+            name: request.name.trim().to_string(),
+            prototypes: request.prototypes,
+            threshold: request.threshold.unwrap_or(DEFAULT_LABEL_THRESHOLD),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.labels
+            .upsert(SavedLabel {
+                label: label.clone(),
+                model: self.model_id().to_string(),
+                embeddings,
+            })
+            .await?;
+
+        Ok(label)
+    }
+
+    pub async fn list_labels(&self) -> Vec<Label> {
+        self.labels.list().await
+    }
+
+    pub async fn delete_label(&self, name: &str) -> Result<Option<Label>> {
+        self.labels.remove(name).await
+    }
+
+    /// Re-labels every stored review from its vectors with the current label
+    /// definitions and rewrites `reviews.jsonl`.
+    pub async fn backfill_labels(&self) -> Result<LabelBackfillReport> {
+        let labels = self.current_labels().await;
 
-        // Add to in-memory storage
         let mut reviews = self.reviews.write().await;
-        let review_index = reviews.len();
-        reviews.push(review.clone());
+        let vector_entries = self.vector_entries.read().await;
+        let review_vectors = self.review_vectors.read().await;
 
-        let mut id_to_review_index = self.id_to_review_index.write().await;
-        id_to_review_index.insert(vector_id, review_index);
+        let mut report = LabelBackfillReport {
+            reviews: reviews.len(),
+            labeled_reviews: 0,
+            counts: labels.iter().map(|saved| (saved.label.name.clone(), 0)).collect(),
+        };
+        for review in reviews.iter_mut() {
+            let vectors: Vec<&[f32]> = review_vectors
+                .get(&review.id)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| vector_entries.get(id))
+                .map(|entry| entry.vector.as_slice())
+                .collect();
+            review.labels = assign_labels(&labels, &vectors);
 
-        // Update next ID
-        let mut next_id = self.next_id.write().await;
-        if vector_id >= *next_id {
-            *next_id = vector_id + 1;
+            if !review.labels.is_empty() {
+                report.labeled_reviews += 1;
+            }
+            for label in &review.labels {
+                *report.counts.entry(label.clone()).or_default() += 1;
+            }
         }
 
-        // Append to file storage
-        self.append_to_storage(&review).await?;
+        self.rewrite_storage(&reviews).await?;
+        Ok(report)
+    }
 
-        Ok(review)
+    /// Label definitions with prototype embeddings from the current model.
+    /// Labels saved under another model are embedded on the fly until
+    /// `refresh_label_embeddings` catches up.
+    async fn current_labels(&self) -> Vec<SavedLabel> {
+        let mut labels = self.labels.saved().await;
+        for saved in labels.iter_mut().filter(|saved| saved.model != self.model_id()) {
+            let prototypes = saved.label.prototypes.iter().map(String::as_str).collect();
+            match self.embedder.embed_batch(prototypes).await {
+                Ok(embeddings) => saved.embeddings = embeddings,
+                Err(e) => {
+                    tracing::warn!("Failed to embed label {}: {}", saved.label.name, e);
+                    saved.embeddings.clear();
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Re-embeds label prototypes saved under a different model.
+    async fn refresh_label_embeddings(&self) -> Result<()> {
+        let mut embeddings = HashMap::new();
+        for saved in self.labels.saved().await {
+            if saved.model != self.model_id() {
+                let prototypes = saved.label.prototypes.iter().map(String::as_str).collect();
+                embeddings.insert(saved.label.name, self.embedder.embed_batch(prototypes).await?);
+            }
+        }
+
+        self.labels.replace_embeddings(self.model_id(), embeddings).await
     }
 
     async fn append_to_storage(&self, review: &Review) -> Result<()> {
@@ -161,50 +890,626 @@ impl SPFreshVectorStore {
         Ok(())
     }
 
-    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<(Review, f32)>> {
-        if let Some(ref index) = *self.index.read().await {
-            // Generate FastEmbed embedding for query
-            let query_vector = self.fastembed_service.embed_text(query)?;
+    /// Catalog products, optionally only those of a category and brand.
+    pub async fn list_products(&self, category: Option<&str>, brand: Option<&str>) -> Vec<Product> {
+        self.catalog.list(category, brand).await
+    }
 
-            // Search using SPFresh
-            let search_results = match index.search(&query_vector, limit) {
-                Ok(results) => results,
-                Err(e) => {
-                    eprintln!("Search failed: {}", e);
-                    return Ok(Vec::new());
+    pub async fn get_product(&self, product_id: &str) -> Option<Product> {
+        self.catalog.get(product_id).await
+    }
+
+    /// Adds a product to the catalog, or returns `None` if the id is taken.
+    pub async fn create_product(&self, request: CreateProductRequest) -> Result<Option<Product>> {
+        self.catalog.create(request).await
+    }
+
+    pub async fn update_product(&self, product_id: &str, fields: ProductFields) -> Result<Option<Product>> {
+        self.catalog.update(product_id, fields).await
+    }
+
+    /// Removes a product from the catalog. Its reviews are kept.
+    pub async fn delete_product(&self, product_id: &str) -> Result<Option<Product>> {
+        self.catalog.remove(product_id).await
+    }
+
+    pub async fn import_products(&self, requests: Vec<CreateProductRequest>) -> Result<ProductImportReport> {
+        self.catalog.import(requests).await
+    }
+
+    /// Removes a review from search results and from `reviews.jsonl`. The
+    /// index is append-only, so its vectors stay behind unmapped and are
+    /// skipped at query time.
+    pub async fn delete_review(&self, review_id: &str) -> Result<Option<Review>> {
+        let Some(review) = self.forget_review(review_id).await else {
+            return Ok(None);
+        };
+
+        {
+            let reviews = self.reviews.read().await;
+            self.rewrite_storage(&reviews).await?;
+        }
+        self.events.publish(EventKind::ReviewDeleted, &review);
+
+        Ok(Some(review))
+    }
+
+    /// Drops a review and its vector mappings from memory only.
+    async fn forget_review(&self, review_id: &str) -> Option<Review> {
+        let mut reviews = self.reviews.write().await;
+        let review_index = reviews.iter().position(|review| review.id == review_id)?;
+        let review = reviews.remove(review_index);
+
+        // Later reviews shift down by one
+        self.review_vectors.write().await.remove(review_id);
+
+        let mut vector_entries = self.vector_entries.write().await;
+        let review_vector = mean_vector(
+            vector_entries
+                .values()
+                .filter(|entry| entry.review_index == review_index)
+                .map(|entry| entry.vector.as_slice()),
+        );
+        if let Err(e) = self.product_index.remove_review(&review.product_id, &review_vector).await {
+            tracing::warn!("Failed to update embedding of product {}: {}", review.product_id, e);
+        }
+        vector_entries.retain(|_, entry| entry.review_index != review_index);
+        for entry in vector_entries.values_mut() {
+            if entry.review_index > review_index {
+                entry.review_index -= 1;
+            }
+        }
+
+        Some(review)
+    }
+
+    async fn rewrite_storage(&self, reviews: &[Review]) -> Result<()> {
+        std::fs::create_dir_all(&self.data_dir)?;
+
+        let metadata_path = format!("{}/reviews.jsonl", self.data_dir);
+        let tmp_path = format!("{}.tmp", metadata_path);
+        let mut file = File::create(&tmp_path)?;
+        for review in reviews {
+            writeln!(file, "{}", serde_json::to_string(review)?)?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &metadata_path)?;
+
+        Ok(())
+    }
+
+    /// Searches title and chunk vectors, then fuses the title score and the
+    /// aggregated chunk scores into one result per review and applies the
+    /// recency and rating boosts. A field the index
+    /// didn't return for a candidate is scored exactly from its stored vectors.
+    /// When filters leave fewer candidates than requested, every review that
+    /// passes them is scored exactly instead. Results are grouped and faceted
+    /// when the request asks for it.
+    pub async fn search_response(&self, request: &SearchRequest) -> Result<SearchResponse> {
+        let run = self.run_search(request).await?;
+        let hits = run.hits.into_iter().map(|(hit, _)| hit).collect();
+        Ok(build_response(request, hits, run.facets))
+    }
+
+    /// Vectors in the product index, compacted or not.
+    #[cfg(test)]
+    pub async fn product_vector_count(&self) -> usize {
+        self.product_index.vector_count().await
+    }
+
+    /// Just the ranked hits of `search_response`.
+    #[cfg(test)]
+    pub async fn search(&self, request: &SearchRequest) -> Result<Vec<ReviewWithScore>> {
+        let run = self.run_search(request).await?;
+        Ok(run.hits.into_iter().map(|(hit, _)| hit).collect())
+    }
+
+    /// Runs `request` like `search_response` and reports how each hit was
+    /// scored: raw index scores next to the cosines computed from the stored
+    /// vectors, where the hit came from, and what the filters decided.
+    pub async fn explain(&self, request: &SearchRequest) -> Result<ExplainResponse> {
+        let run = self.run_search(request).await?;
+        let aggregation = request.chunk_aggregation.unwrap_or_default();
+        let field_weights = request.field_weights.unwrap_or_default();
+        let ranking = self.config.ranking.with_overrides(request.ranking.as_ref());
+        let now = run.now;
+
+        let reviews = self.reviews.read().await;
+        let vector_entries = self.vector_entries.read().await;
+        let review_vectors = self.review_vectors.read().await;
+
+        let explained = run
+            .hits
+            .into_iter()
+            .map(|(hit, source)| {
+                let vectors: Vec<VectorScore> = review_vectors
+                    .get(&hit.review.id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|&vector_id| {
+                        let entry = vector_entries.get(&vector_id)?;
+                        let (field, span) = match entry.source {
+                            VectorSource::Title => (ReviewField::Title, None),
+                            VectorSource::Body(span) => (ReviewField::Body, Some(span)),
+                        };
+                        Some(VectorScore {
+                            vector_id,
+                            field,
+                            start: span.map(|span| span.start),
+                            end: span.map(|span| span.end),
+                            ann_distance: run.ann_distances.get(&vector_id).copied(),
+                            exact_cosine: cosine_similarity(&run.query_vector, &entry.vector),
+                        })
+                    })
+                    .collect();
+
+                let exact_title = vectors
+                    .iter()
+                    .filter(|vector| vector.field == ReviewField::Title)
+                    .map(|vector| vector.exact_cosine)
+                    .fold(None, |best: Option<f32>, score| Some(best.map_or(score, |best| best.max(score))));
+                let mut exact_chunks: Vec<f32> = vectors
+                    .iter()
+                    .filter(|vector| vector.field == ReviewField::Body)
+                    .map(|vector| vector.exact_cosine)
+                    .collect();
+                exact_chunks.sort_by(|a, b| b.total_cmp(a));
+                let exact_semantic = field_weights.fuse(exact_title.unwrap_or(0.0), aggregation.combine(&exact_chunks));
+                let exact_score = ranking.score(exact_semantic, &hit.review, now).final_score;
+
+                ExplainedHit {
+                    filters: run.filters.decisions(&hit.review),
+                    similarity_score: hit.similarity_score,
+                    field_scores: hit.field_scores.unwrap_or(FieldScores { title: 0.0, body: 0.0 }),
+                    score_breakdown: hit.score_breakdown,
+                    source,
+                    ann_distance: vectors
+                        .iter()
+                        .filter(|vector| vector.ann_distance.is_some())
+                        .max_by(|a, b| a.exact_cosine.total_cmp(&b.exact_cosine))
+                        .and_then(|vector| vector.ann_distance),
+                    exact_score,
+                    vectors,
+                    review: hit.review,
                 }
+            })
+            .collect();
+
+        let rejected = run
+            .rejected
+            .iter()
+            .filter_map(|review_id| {
+                let review = reviews.iter().find(|review| &review.id == review_id)?;
+                Some(RejectedCandidate {
+                    review_id: review_id.clone(),
+                    filters: run.filters.decisions(review),
+                })
+            })
+            .collect();
+
+        Ok(ExplainResponse {
+            query: request.query.clone(),
+            reviews: explained,
+            rejected,
+            ann_candidates: run.ann_distances.len(),
+            fallback_scan: run.fallback_scan,
+        })
+    }
+
+    /// Normalizes `filters`, resolving catalog filters to product ids.
+    async fn search_filters(&self, filters: &ReviewFilters) -> SearchFilters {
+        let category = filters.category.as_deref();
+        let brand = filters.brand.as_deref();
+        SearchFilters::new(
+            filters,
+            self.catalog.resolve(category, None).await,
+            self.catalog.resolve(None, brand).await,
+        )
+    }
+
+    async fn run_search(&self, request: &SearchRequest) -> Result<SearchRun> {
+        let aggregation = request.chunk_aggregation.unwrap_or_default();
+        let field_weights = request.field_weights.unwrap_or_default();
+        let filters = self.search_filters(&request.filters).await;
+        let ranking = self.config.ranking.with_overrides(request.ranking.as_ref());
+        let now = chrono::Utc::now();
+
+        // Grouped searches rank enough reviews to fill every group
+        let limit = match request.group_by {
+            Some(_) => grouped_candidates(request),
+            None => request.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        };
+
+        // Diversity re-ranking picks from a larger pool of relevant reviews
+        let diversity = request.diversity.unwrap_or(0.0);
+        let pool = if diversity > 0.0 { limit * DIVERSITY_POOL_PER_RESULT } else { limit };
+
+        let mut run = SearchRun {
+            hits: Vec::new(),
+            query_vector: Vec::new(),
+            ann_distances: HashMap::new(),
+            rejected: Vec::new(),
+            fallback_scan: false,
+            filters,
+            now,
+            facets: None,
+        };
+
+        // Generate FastEmbed embedding for query, before locking the index so
+        // inserts aren't held up while it waits for a batch
+        run.query_vector = self.embedder.embed_query(&request.query).await?;
+
+        let index_guard = self.index.read().await;
+        let Some(ref index) = *index_guard else {
+            return Ok(run);
+        };
+
+        // Deleted reviews leave unmapped vectors behind, ask for enough extra
+        // candidates to still fill the page
+        let unmapped = index
+            .get_vector_count()
+            .saturating_sub(self.vector_entries.read().await.len());
+
+        // Search using SPFresh
+        let search_results = match index.search(&run.query_vector, pool * CANDIDATES_PER_RESULT + unmapped) {
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("Search failed: {}", e);
+                return Ok(run);
+            }
+        };
+
+        // Group hits by review
+        let reviews = self.reviews.read().await;
+        let vector_entries = self.vector_entries.read().await;
+        let review_vectors = self.review_vectors.read().await;
+        let cluster_ids = self.clusters.assignments().await;
+        let products = self.catalog.products().await;
+
+        let mut hits: HashMap<usize, (FieldHits, HitSource)> = HashMap::new();
+        let mut rejected = HashSet::new();
+        for (vector_id, distance) in search_results {
+            let Some(entry) = vector_entries.get(&vector_id) else {
+                continue;
             };
+            let Some(review) = reviews.get(entry.review_index) else {
+                continue;
+            };
+            run.ann_distances.insert(vector_id, distance);
 
-            // Convert results to reviews
-            let reviews = self.reviews.read().await;
-            let id_to_review_index = self.id_to_review_index.read().await;
-            
-            let mut results = Vec::new();
-            for (vector_id, distance) in search_results {
-                if let Some(&review_index) = id_to_review_index.get(&vector_id) {
-                    if let Some(review) = reviews.get(review_index) {
-                        // Convert distance to similarity (assuming distance is based on cosine or similar)
-                        let similarity = 1.0 - distance;
-                        results.push((review.clone(), similarity));
-                    }
+            if !run.filters.matches(review) {
+                if rejected.insert(entry.review_index) {
+                    run.rejected.push(review.id.clone());
                 }
+                continue;
             }
 
-            Ok(results)
+            // The index only picks candidates. Depending on the wrapper it
+            // reports a distance or a similarity, so the score is the exact
+            // cosine against the stored vector, the same as for fill-ins and
+            // the fallback scan
+            let score = cosine_similarity(&run.query_vector, &entry.vector);
+            let (found, _) = hits
+                .entry(entry.review_index)
+                .or_insert_with(|| (FieldHits::default(), HitSource::Ann));
+            match entry.source {
+                VectorSource::Title => found.add_title(score),
+                VectorSource::Body(span) => found.add_chunk(span, score),
+            }
+        }
+
+        if run.filters.is_active() && hits.len() < pool {
+            run.fallback_scan = true;
+            for (review_index, review) in reviews.iter().enumerate() {
+                if run.filters.matches(review) {
+                    hits.entry(review_index)
+                        .or_insert_with(|| (FieldHits::default(), HitSource::FallbackScan));
+                }
+            }
+        }
+
+        let mut results: Vec<(ReviewWithScore, HitSource, Vec<f32>)> = hits
+            .into_iter()
+            .filter_map(|(review_index, (mut found, source))| {
+                let review = reviews.get(review_index)?;
+
+                // Fill in fields that missed the candidate list
+                let stored: Vec<&VectorEntry> = review_vectors
+                    .get(&review.id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| vector_entries.get(id))
+                    .collect();
+                if found.title.is_none() {
+                    for entry in stored.iter().filter(|entry| entry.source == VectorSource::Title) {
+                        found.add_title(cosine_similarity(&run.query_vector, &entry.vector));
+                    }
+                }
+                if found.chunks.is_empty() {
+                    for entry in &stored {
+                        if let VectorSource::Body(span) = entry.source {
+                            found.add_chunk(span, cosine_similarity(&run.query_vector, &entry.vector));
+                        }
+                    }
+                }
+
+                let mut matched_chunks = found.chunks;
+                matched_chunks.sort_by(|a, b| b.score.total_cmp(&a.score));
+                let chunk_scores: Vec<f32> = matched_chunks.iter().map(|chunk| chunk.score).collect();
+
+                let field_scores = FieldScores {
+                    title: found.title.unwrap_or(0.0),
+                    body: aggregation.combine(&chunk_scores),
+                };
+
+                let semantic = field_weights.fuse(field_scores.title, field_scores.body);
+                let score_breakdown = ranking.score(semantic, review, now);
+
+                Some((
+                    ReviewWithScore {
+                        review: review.clone(),
+                        similarity_score: score_breakdown.final_score,
+                        field_scores: Some(field_scores),
+                        score_breakdown: Some(score_breakdown),
+                        matched_chunks,
+                        cluster_id: cluster_ids.get(&review.id).copied(),
+                        product: products.get(&review.product_id).cloned(),
+                    },
+                    source,
+                    mean_vector(stored.iter().map(|entry| entry.vector.as_slice())),
+                ))
+            })
+            .collect();
+
+        // Facets count every candidate that passed the filters, not just the page
+        if let Some(facets) = &request.facets {
+            run.facets = Some(facet_counts(facets, results.iter().map(|(hit, _, _)| &hit.review)));
+        }
+
+        results.sort_by(|a, b| b.0.similarity_score.total_cmp(&a.0.similarity_score));
+        results.truncate(pool);
+
+        if diversity > 0.0 {
+            let scored = results
+                .into_iter()
+                .map(|(hit, source, vector)| {
+                    let relevance = hit.similarity_score;
+                    ((hit, source), relevance, vector)
+                })
+                .collect();
+            run.hits = mmr_select(scored, diversity, limit);
         } else {
-            Ok(Vec::new())
+            run.hits = results.into_iter().map(|(hit, source, _)| (hit, source)).collect();
         }
+
+        Ok(run)
     }
 
-    pub async fn get_stats(&self) -> (usize, usize) {
-        let reviews = self.reviews.read().await;
+    /// Matches every clause of a product's reviews against the configured
+    /// aspects and scores the sentiment of the ones that match. Clauses are
+    /// embedded on request, so repeat calls lean on the embedding cache.
+    /// `None` if the product has no reviews.
+    pub async fn product_aspects(&self, product_id: &str) -> Result<Option<ProductAspects>> {
+        let reviews: Vec<Review> = self
+            .reviews
+            .read()
+            .await
+            .iter()
+            .filter(|review| review.product_id == product_id)
+            .cloned()
+            .collect();
+        if reviews.is_empty() {
+            return Ok(None);
+        }
+
+        let config = &self.config.aspects;
+        let mut aspect_vectors = Vec::with_capacity(config.aspects.len());
+        for aspect in &config.aspects {
+            aspect_vectors.push(self.embedder.embed_query(&aspect.description).await?);
+        }
+
+        let clauses: Vec<(&str, &str)> = reviews
+            .iter()
+            .flat_map(|review| {
+                let title = split_clauses(&review.review_title)
+                    .into_iter()
+                    .map(|span| &review.review_title[span.start..span.end]);
+                let body = split_clauses(&review.review_body)
+                    .into_iter()
+                    .map(|span| &review.review_body[span.start..span.end]);
+                title.chain(body).map(|clause| (review.id.as_str(), clause))
+            })
+            .collect();
+        let embeddings = if clauses.is_empty() {
+            Vec::new()
+        } else {
+            self.embedder
+                .embed_batch(clauses.iter().map(|(_, clause)| *clause).collect())
+                .await?
+        };
+
+        let mut mentions: Vec<Vec<Mention>> = config.aspects.iter().map(|_| Vec::new()).collect();
+        for ((review_id, clause), embedding) in clauses.iter().zip(&embeddings) {
+            for (aspect_mentions, aspect_vector) in mentions.iter_mut().zip(&aspect_vectors) {
+                let similarity = cosine_similarity(embedding, aspect_vector);
+                if similarity >= config.threshold {
+                    aspect_mentions.push(Mention {
+                        review_id: review_id.to_string(),
+                        text: clause.to_string(),
+                        sentiment: self.config.sentiment.score(clause),
+                        similarity,
+                    });
+                }
+            }
+        }
+
+        let mut aspects: Vec<_> = config
+            .aspects
+            .iter()
+            .zip(mentions)
+            .filter(|(_, mentions)| !mentions.is_empty())
+            .map(|(aspect, mentions)| aspects::summarize(&aspect.name, mentions))
+            .collect();
+        aspects.sort_by_key(|aspect| std::cmp::Reverse(aspect.mentions));
+
+        Ok(Some(ProductAspects {
+            product_id: product_id.to_string(),
+            reviews: reviews.len(),
+            aspects,
+        }))
+    }
+
+    /// Counts a product's reviews by rating and picks the most representative
+    /// positive (4-5★) and negative (1-2★) ones: those whose mean vector is
+    /// closest to the centroid of their group. `None` if the product has no
+    /// reviews.
+    pub async fn product_summary(&self, product_id: &str, representatives: usize) -> Result<Option<ProductSummary>> {
+        let (reviews, vectors): (Vec<Review>, Vec<Vec<f32>>) = {
+            let reviews = self.reviews.read().await;
+            let vector_entries = self.vector_entries.read().await;
+            let review_vectors = self.review_vectors.read().await;
+
+            reviews
+                .iter()
+                .filter(|review| review.product_id == product_id)
+                .map(|review| {
+                    let vector = review_vectors
+                        .get(&review.id)
+                        .map(|ids| {
+                            mean_vector(
+                                ids.iter()
+                                    .filter_map(|id| vector_entries.get(id))
+                                    .map(|entry| entry.vector.as_slice()),
+                            )
+                        })
+                        .unwrap_or_default();
+                    (review.clone(), vector)
+                })
+                .unzip()
+        };
+        if reviews.is_empty() {
+            return Ok(None);
+        }
+
+        let mut rating_histogram: BTreeMap<u8, usize> = (1..=5).map(|rating| (rating, 0)).collect();
+        for review in &reviews {
+            *rating_histogram.entry(review.review_rating.clamp(1, 5)).or_default() += 1;
+        }
+        let average_rating =
+            reviews.iter().map(|review| review.review_rating as f32).sum::<f32>() / reviews.len() as f32;
+
+        let positive = representative_reviews(&reviews, &vectors, |rating| rating >= 4, representatives);
+        let negative = representative_reviews(&reviews, &vectors, |rating| rating <= 2, representatives);
+
+        Ok(Some(ProductSummary {
+            product_id: product_id.to_string(),
+            product: self.catalog.get(product_id).await,
+            reviews: reviews.len(),
+            average_rating,
+            rating_histogram,
+            positive,
+            negative,
+        }))
+    }
+
+    /// Products whose reviews read most like the product's, by the cosine
+    /// similarity of their product embeddings. `None` if the product has no
+    /// reviews.
+    pub async fn similar_products(&self, product_id: &str, limit: usize) -> Result<Option<Vec<SimilarProduct>>> {
+        let Some(similar) = self.product_index.similar(product_id, limit).await? else {
+            return Ok(None);
+        };
+
+        let products = self.catalog.products().await;
+        Ok(Some(
+            similar
+                .into_iter()
+                .map(|(product_id, similarity, reviews)| SimilarProduct {
+                    product: products.get(&product_id).cloned(),
+                    product_id,
+                    similarity,
+                    reviews,
+                })
+                .collect(),
+        ))
+    }
+
+    /// Clusters the mean vectors of the reviews in the request's scope and
+    /// replaces the stored assignments with the result.
+    pub async fn cluster_reviews(&self, request: ClusterRequest) -> Result<ClusterRun> {
+        let scope = ClusterScope::new(&request)?;
+
+        let (members, points): (Vec<Review>, Vec<Vec<f32>>) = {
+            let reviews = self.reviews.read().await;
+            let vector_entries = self.vector_entries.read().await;
+            let review_vectors = self.review_vectors.read().await;
+
+            reviews
+                .iter()
+                .filter(|review| scope.contains(review))
+                .filter_map(|review| {
+                    let vectors = review_vectors.get(&review.id)?;
+                    let point = mean_vector(
+                        vectors
+                            .iter()
+                            .filter_map(|id| vector_entries.get(id))
+                            .map(|entry| entry.vector.as_slice()),
+                    );
+                    (!point.is_empty()).then(|| (review.clone(), point))
+                })
+                .unzip()
+        };
+
+        let k = request.k.unwrap_or(clustering::DEFAULT_CLUSTERS);
+        let method = request.method;
+        let max_iterations = request.max_iterations.unwrap_or(clustering::DEFAULT_MAX_ITERATIONS);
+
+        // k-means is CPU bound, keep it off the async workers
+        let (clusters, assignments, iterations) = tokio::task::spawn_blocking(move || {
+            let result = clustering::kmeans(&points, k, method, max_iterations);
+            let clusters = clustering::summarize(&members, &points, &result);
+            let assignments: HashMap<String, usize> = members
+                .into_iter()
+                .zip(result.assignments)
+                .map(|(review, cluster_id)| (review.id, cluster_id))
+                .collect();
+            (clusters, assignments, result.iterations)
+        })
+        .await?;
+
+        let run = ClusterRun {
+            id: Uuid::new_v4().to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            model: self.model_id().to_string(),
+            request,
+            reviews_clustered: assignments.len(),
+            iterations,
+            clusters,
+        };
+        self.clusters.replace(run.clone(), assignments).await?;
+
+        Ok(run)
+    }
+
+    pub async fn latest_clusters(&self) -> Option<ClusterRun> {
+        self.clusters.latest().await
+    }
+
+    pub async fn get_stats(&self) -> StatsResponse {
+        // The index is locked before the reviews, like everywhere else
         let vector_count = if let Some(ref index) = *self.index.read().await {
             index.get_vector_count()
         } else {
             0
         };
-        
-        (reviews.len(), vector_count)
+        let reviews = self.reviews.read().await;
+
+        StatsResponse {
+            total_reviews: reviews.len(),
+            total_vectors: vector_count,
+            embedding_cache: self.embedder.cache_stats(),
+            sentiment: sentiment_stats(&reviews),
+        }
     }
 
     pub async fn is_ready(&self) -> bool {
@@ -212,3 +1517,181 @@ impl SPFreshVectorStore {
         index.is_some()
     }
 }
+
+/// Renames `from` to `to`, moving an existing `to` aside to `retired` first
+/// and back if the rename fails.
+fn replace_dir(from: &Path, to: &Path, retired: &Path) -> Result<()> {
+    if retired.exists() {
+        std::fs::remove_dir_all(retired)?;
+    }
+    let had_target = to.exists();
+    if had_target {
+        std::fs::rename(to, retired)?;
+    }
+
+    if let Err(e) = std::fs::rename(from, to) {
+        if had_target {
+            std::fs::rename(retired, to)?;
+        }
+        return Err(e.into());
+    }
+
+    if had_target {
+        std::fs::remove_dir_all(retired)?;
+    }
+    Ok(())
+}
+
+/// A search's ranked hits plus what `explain` needs to report on them.
+struct SearchRun {
+    hits: Vec<(ReviewWithScore, HitSource)>,
+    query_vector: Vec<f32>,
+    /// Raw index distance of every mapped vector the index returned.
+    ann_distances: HashMap<i32, f32>,
+    /// Ids of index candidates removed by filters, in rank order.
+    rejected: Vec<String>,
+    fallback_scan: bool,
+    filters: SearchFilters,
+    /// Reference time for recency boosts, shared by `explain`'s rescoring.
+    now: chrono::DateTime<chrono::Utc>,
+    facets: Option<BTreeMap<String, Vec<FacetBucket>>>,
+}
+
+/// Mean of a stored review's title and chunk vectors; empty if it has none.
+fn stored_review_vector(
+    review_id: &str,
+    vector_entries: &HashMap<i32, VectorEntry>,
+    review_vectors: &HashMap<String, Vec<i32>>,
+) -> Vec<f32> {
+    mean_vector(
+        review_vectors
+            .get(review_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| vector_entries.get(id))
+            .map(|entry| entry.vector.as_slice()),
+    )
+}
+
+/// Indexes of the stored reviews, other than `exclude`, whose mean vector is
+/// at least `threshold` similar to `review_vector`, with their similarity.
+fn near_duplicates(
+    index: &SPFreshIndexWrapper,
+    reviews: &[Review],
+    vector_entries: &HashMap<i32, VectorEntry>,
+    review_vectors: &HashMap<String, Vec<i32>>,
+    review_vector: &[f32],
+    exclude: Option<usize>,
+    threshold: f32,
+) -> Result<Vec<(usize, f32)>> {
+    if review_vector.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Deleted reviews leave unmapped vectors behind
+    let unmapped = index.get_vector_count().saturating_sub(vector_entries.len());
+    let results = index
+        .search(review_vector, DUPLICATE_CANDIDATES + unmapped)
+        .map_err(|e| anyhow::anyhow!("Duplicate search failed: {}", e))?;
+
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    for (vector_id, _) in results {
+        let Some(entry) = vector_entries.get(&vector_id) else {
+            continue;
+        };
+        if Some(entry.review_index) == exclude || !seen.insert(entry.review_index) {
+            continue;
+        }
+        let Some(review) = reviews.get(entry.review_index) else {
+            continue;
+        };
+
+        let candidate = stored_review_vector(&review.id, vector_entries, review_vectors);
+        let similarity = cosine_similarity(review_vector, &candidate);
+        if similarity >= threshold {
+            found.push((entry.review_index, similarity));
+        }
+    }
+
+    Ok(found)
+}
+
+/// Up to `limit` of the reviews with a rating in the group, closest first to
+/// the group's centroid. Reviews without stored vectors are skipped.
+fn representative_reviews(
+    reviews: &[Review],
+    vectors: &[Vec<f32>],
+    in_group: impl Fn(u8) -> bool,
+    limit: usize,
+) -> Vec<RepresentativeReview> {
+    let members: Vec<(&Review, &Vec<f32>)> = reviews
+        .iter()
+        .zip(vectors)
+        .filter(|(review, vector)| in_group(review.review_rating) && !vector.is_empty())
+        .collect();
+    let centroid = mean_vector(members.iter().map(|(_, vector)| vector.as_slice()));
+
+    let mut representatives: Vec<RepresentativeReview> = members
+        .into_iter()
+        .map(|(review, vector)| RepresentativeReview {
+            review: review.clone(),
+            similarity: cosine_similarity(vector, &centroid),
+        })
+        .collect();
+    representatives.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    representatives.truncate(limit);
+    representatives
+}
+
+fn sentiment_stats(reviews: &[Review]) -> SentimentStats {
+    let mut stats = SentimentStats {
+        by_rating: (1..=5)
+            .map(|rating| RatingSentiment {
+                rating,
+                ..RatingSentiment::default()
+            })
+            .collect(),
+        ..SentimentStats::default()
+    };
+
+    let mut total = 0.0;
+    let mut scored = 0;
+    for review in reviews {
+        let Some(sentiment) = review.sentiment else {
+            continue;
+        };
+        total += sentiment;
+        scored += 1;
+
+        let rating = review.review_rating.clamp(1, 5);
+        let bucket = &mut stats.by_rating[rating as usize - 1];
+        bucket.reviews += 1;
+        bucket.mean_sentiment += sentiment;
+
+        if sentiment >= POLARITY_THRESHOLD {
+            bucket.positive += 1;
+            if rating <= 2 {
+                stats.positive_text_low_rating += 1;
+            }
+        } else if sentiment <= -POLARITY_THRESHOLD {
+            bucket.negative += 1;
+            if rating >= 4 {
+                stats.negative_text_high_rating += 1;
+            }
+        } else {
+            bucket.neutral += 1;
+        }
+    }
+
+    for bucket in &mut stats.by_rating {
+        if bucket.reviews > 0 {
+            bucket.mean_sentiment /= bucket.reviews as f32;
+        }
+    }
+    if scored > 0 {
+        stats.mean = total / scored as f32;
+    }
+
+    stats
+}
@@ -1,17 +1,42 @@
 use anyhow::Result;
-use crate::fastembed_service::FastEmbedService;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use crate::embedder::{Embedder, HashEmbedder};
+use crate::handlers::create_router;
+use crate::spfresh_vector_store::{SPFreshVectorStore, StoreConfig};
+use crate::chunking::{chunk_body, split_sentences, ChunkConfig};
+use crate::vector_store::VectorStore;
+use crate::embed_cache::EmbeddingCache;
+use crate::manifest::{IndexManifest, INDEX_FORMAT_VERSION};
+use crate::fastembed_service::{
+    parse_pooling, BatchConfig, BatchQueue, EmbeddingConfig, FastEmbedService, ModelSource, TextPrefixes,
+};
+use crate::language::{detect_language, normalize_language};
+use crate::sentiment::SentimentAnalyzer;
+use crate::aspects::{parse_aspects, split_clauses, AspectConfig};
+use crate::duplicates::{group_pairs, parse_duplicate_mode, DuplicateConfig, DuplicateMode};
+use crate::models::{ReviewFilters, SearchRequest};
+use crate::redaction::{parse_detector, parse_redaction_mode, RedactionConfig, RedactionMode};
+use fastembed::{EmbeddingModel, Pooling};
 
 #[tokio::test]
 async fn test_fastembed_service() -> Result<()> {
     // Test FastEmbed service initialization
-    let service = FastEmbedService::new()?;
+    let service = FastEmbedService::new(EmbeddingConfig::default())?;
     
     // Test embedding generation
     let text = "This is a test review about a great product";
-    let embedding = service.embed_text(text)?;
+    let embedding = service.embed_text(text).await?;
     
     // Check embedding dimensions (BGE-small-en-v1.5 should produce 384-dimensional embeddings)
     assert_eq!(embedding.len(), 384);
+    assert_eq!(service.dimension(), 384);
     
     // Check that embedding values are not all zeros
     assert!(embedding.iter().any(|&x| x != 0.0));
@@ -24,7 +49,7 @@ async fn test_fastembed_service() -> Result<()> {
 
 #[tokio::test]
 async fn test_fastembed_batch() -> Result<()> {
-    let service = FastEmbedService::new()?;
+    let service = FastEmbedService::new(EmbeddingConfig::default())?;
     
     let texts = vec![
         "This is a positive review",
@@ -32,7 +57,7 @@ async fn test_fastembed_batch() -> Result<()> {
         "This is a neutral review"
     ];
     
-    let embeddings = service.embed_batch(texts)?;
+    let embeddings = service.embed_batch(texts).await?;
     
     // Check that we got 3 embeddings
     assert_eq!(embeddings.len(), 3);
@@ -46,3 +71,1950 @@ async fn test_fastembed_batch() -> Result<()> {
     
     Ok(())
 }
+
+
+#[tokio::test]
+async fn test_fastembed_concurrent_requests_are_batched() -> Result<()> {
+    let service = FastEmbedService::new(EmbeddingConfig::default())?;
+
+    let texts: Vec<String> = (0..50)
+        .map(|i| format!("Review number {} about battery life", i))
+        .collect();
+
+    // Fire all requests at once so the worker coalesces them
+    let handles: Vec<_> = texts
+        .iter()
+        .cloned()
+        .map(|text| {
+            let service = service.clone();
+            tokio::spawn(async move { service.embed_text(&text).await })
+        })
+        .collect();
+
+    let mut concurrent = Vec::new();
+    for handle in handles {
+        concurrent.push(handle.await??);
+    }
+
+    // Each caller must get back the embedding for its own text
+    let expected = service.embed_batch(texts.iter().map(String::as_str).collect()).await?;
+    for (got, want) in concurrent.iter().zip(&expected) {
+        assert_eq!(got.len(), want.len());
+        let max_diff = got
+            .iter()
+            .zip(want)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_diff < 1e-4, "embedding differs by {}", max_diff);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_queue_merges_single_texts_and_batches() -> Result<()> {
+    // Stands in for the model: counts its calls and embeds each text as its length
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let queue = BatchQueue::start(
+        move |texts: Vec<String>| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        },
+        BatchConfig { max_batch_size: 64, max_wait: std::time::Duration::from_millis(50) },
+    );
+
+    // Searches embed one text, inserts a title and several chunks
+    let callers: Vec<_> = (0..16)
+        .map(|i| {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                if i % 2 == 0 {
+                    queue.embed("q".repeat(i + 1)).await.map(|embedding| vec![embedding])
+                } else {
+                    queue.embed_all(vec!["t".repeat(i + 1), "c".repeat(2 * i + 1)]).await
+                }
+            })
+        })
+        .collect();
+
+    for (i, caller) in callers.into_iter().enumerate() {
+        let embeddings = caller.await??;
+        if i % 2 == 0 {
+            assert_eq!(embeddings, vec![vec![(i + 1) as f32]]);
+        } else {
+            assert_eq!(embeddings, vec![vec![(i + 1) as f32], vec![(2 * i + 1) as f32]]);
+        }
+    }
+    assert!(calls.load(std::sync::atomic::Ordering::SeqCst) < 16);
+
+    Ok(())
+}
+
+#[test]
+fn test_model_source_from_spec() -> Result<()> {
+    let builtin = ModelSource::from_spec("BGESmallENV15", Pooling::Mean)?;
+    assert_eq!(builtin, ModelSource::Builtin(EmbeddingModel::BGESmallENV15));
+    assert_eq!(ModelSource::from_spec("Xenova/bge-small-en-v1.5", Pooling::Mean)?, builtin);
+
+    // The model id round-trips through the parser
+    assert_eq!(ModelSource::from_spec(&builtin.model_id(), Pooling::Mean)?, builtin);
+
+    let local = ModelSource::from_spec("local:/models/e5", parse_pooling("CLS")?)?;
+    assert_eq!(
+        local,
+        ModelSource::Local { dir: "/models/e5".into(), pooling: Pooling::Cls }
+    );
+    assert_eq!(local.model_id(), "local:/models/e5");
+
+    assert!(ModelSource::from_spec("no-such/model", Pooling::Mean).is_err());
+    assert!(parse_pooling("max").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_manifest_round_trip_and_compatibility() -> Result<()> {
+    let data_dir = std::env::temp_dir().join(format!("manifest-test-{}", uuid::Uuid::new_v4()));
+    let data_dir = data_dir.to_str().unwrap();
+
+    assert!(IndexManifest::load(data_dir)?.is_none());
+
+    let manifest = IndexManifest::new("Xenova/bge-small-en-v1.5", 384);
+    manifest.save(data_dir)?;
+    let loaded = IndexManifest::load(data_dir)?.expect("manifest was saved");
+    assert_eq!(loaded, manifest);
+    assert_eq!(loaded.index_format_version, INDEX_FORMAT_VERSION);
+
+    // Same model is fine, a different model or dimension needs a reindex
+    assert!(loaded.check_compatible(&IndexManifest::new("Xenova/bge-small-en-v1.5", 384)).is_ok());
+    let err = loaded
+        .check_compatible(&IndexManifest::new("intfloat/multilingual-e5-small", 384))
+        .unwrap_err();
+    assert!(err.to_string().contains("reindex --model intfloat/multilingual-e5-small"));
+    assert!(loaded.check_compatible(&IndexManifest::new("Xenova/bge-small-en-v1.5", 768)).is_err());
+
+    std::fs::remove_dir_all(data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reindex_builds_in_staging_until_promoted() -> Result<()> {
+    let data_dir = temp_data_dir("reindex-test");
+    let review = |title: &str, body: &str| crate::models::InsertReviewRequest {
+        review_title: title.to_string(),
+        review_body: body.to_string(),
+        product_id: "PHONE-1".to_string(),
+        review_rating: 4,
+    };
+    let query = |text: &str| SearchRequest {
+        query: text.to_string(),
+        limit: Some(1),
+        ..SearchRequest::default()
+    };
+
+    let old = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(32), StoreConfig::default()).await?;
+    old.insert_review(review("Battery drains", "The battery is empty by noon")).await?;
+    old.insert_review(review("Great camera", "Photos are sharp even at night")).await?;
+
+    // The new model's index is built next to the serving one
+    let mut rebuilt = old.rebuild_with(HashEmbedder::new(48)).await?;
+    assert!(std::path::Path::new(&data_dir).join("index.staging/reviews.index").exists());
+    assert_eq!(rebuilt.get_stats().await.total_vectors, 4);
+
+    // The old store keeps serving, and writing, until the swap
+    old.insert_review(review("Loud speaker", "The speaker is loud and clear")).await?;
+    assert_eq!(old.search(&query("loud speaker")).await?[0].review.review_title, "Loud speaker");
+    assert_eq!(old.search(&query("camera photos")).await?[0].review.review_title, "Great camera");
+    assert_eq!(old.get_stats().await.total_vectors, 6);
+
+    // Labels backfilled on the serving store carry over to the rebuilt one
+    old.upsert_label(crate::models::CreateLabelRequest {
+        name: "battery".to_string(),
+        prototypes: vec!["Battery drains".to_string()],
+        threshold: Some(0.9),
+    })
+    .await?;
+    old.backfill_labels().await?;
+
+    rebuilt.catch_up(&old).await?;
+    let labels = rebuilt.search(&query("battery empty")).await?[0].review.labels.clone();
+    assert_eq!(labels, ["battery"]);
+    rebuilt.promote(&old).await?;
+    assert!(!std::path::Path::new(&data_dir).join("index.staging").exists());
+    assert_eq!(rebuilt.dimension(), 48);
+    assert_eq!(rebuilt.get_stats().await.total_vectors, 6);
+    assert_eq!(rebuilt.search(&query("loud speaker")).await?[0].review.review_title, "Loud speaker");
+
+    // The next start with the new model indexes every review once
+    drop(rebuilt);
+    drop(old);
+    let restarted = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(48), StoreConfig::default()).await?;
+    assert_eq!(restarted.get_stats().await.total_vectors, 6);
+    assert_eq!(restarted.search(&query("battery empty")).await?[0].review.review_title, "Battery drains");
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_embedding_cache_tiers_and_stats() -> Result<()> {
+    let disk_dir = std::env::temp_dir().join(format!("embed-cache-test-{}", uuid::Uuid::new_v4()));
+
+    // Keys depend on the model and ignore whitespace differences
+    let key = EmbeddingCache::key("model-a", "Great  battery life ");
+    assert_eq!(key, EmbeddingCache::key("model-a", "Great battery life"));
+    assert_ne!(key, EmbeddingCache::key("model-b", "Great battery life"));
+
+    let cache = EmbeddingCache::new(1, Some(disk_dir.clone()));
+    assert!(cache.get(&key).is_none());
+    cache.put(&key, &[0.25, -1.0, 3.5]);
+    assert_eq!(cache.get(&key), Some(vec![0.25, -1.0, 3.5]));
+
+    // Capacity 1: the second entry evicts the first from memory, disk still has it
+    let other = EmbeddingCache::key("model-a", "Screen cracked");
+    cache.put(&other, &[1.0]);
+    assert_eq!(cache.get(&key), Some(vec![0.25, -1.0, 3.5]));
+
+    let stats = cache.stats();
+    assert_eq!((stats.memory_hits, stats.disk_hits, stats.misses), (1, 1, 1));
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.memory_entries, 1);
+
+    // A new cache over the same directory starts warm
+    let reopened = EmbeddingCache::new(10, Some(disk_dir.clone()));
+    assert_eq!(reopened.get(&other), Some(vec![1.0]));
+    assert_eq!(reopened.stats().disk_hits, 1);
+
+    std::fs::remove_dir_all(disk_dir)?;
+    Ok(())
+}
+
+fn temp_data_dir(prefix: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("{}-{}", prefix, uuid::Uuid::new_v4()))
+        .to_string_lossy()
+        .into_owned()
+}
+
+async fn test_router(data_dir: &str) -> Result<Router> {
+    let store = SPFreshVectorStore::new(data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    Ok(create_router(Arc::new(RwLock::new(store))))
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> Result<(StatusCode, Value)> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))?;
+
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    let json = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes)? };
+
+    Ok((status, json))
+}
+
+#[tokio::test]
+async fn test_hash_embedder_is_deterministic() -> Result<()> {
+    let embedder = HashEmbedder::new(64);
+
+    let a = embedder.embed("Battery died after two days").await?;
+    let b = embedder.embed("battery DIED after two days!").await?;
+    assert_eq!(a, b);
+    assert_eq!(a.len(), embedder.dimension());
+
+    let batch = embedder.embed_batch(vec!["Battery died after two days", "Lovely screen"]).await?;
+    assert_eq!(batch[0], a);
+    assert_ne!(batch[1], a);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_insert_search_delete_through_router() -> Result<()> {
+    let data_dir = temp_data_dir("router-test");
+    let app = test_router(&data_dir).await?;
+
+    let (status, battery) = send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Battery drains fast",
+        "review_body": "The battery is empty by lunch every day",
+        "product_id": "PHONE-1",
+        "review_rating": 2
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let battery_id = battery["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(&app, "POST", "/reviews/bulk", Some(json!([
+        {
+            "review_title": "Gorgeous screen",
+            "review_body": "Colors on the display are vivid",
+            "product_id": "PHONE-1",
+            "review_rating": 5
+        },
+        {
+            "review_title": "Slow shipping",
+            "review_body": "Delivery took three weeks",
+            "product_id": "CASE-9",
+            "review_rating": 3
+        }
+    ]))).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    assert_eq!(stats["data"]["total_reviews"], 3);
+
+    let (status, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery empty",
+        "limit": 1
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["data"]["reviews"][0]["review"]["id"], battery_id.as_str());
+
+    let (status, _) = send(&app, "DELETE", &format!("/reviews/{}", battery_id), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &format!("/reviews/{}", battery_id), None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery empty" }))).await?;
+    let ids: Vec<&str> = results["data"]["reviews"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["review"]["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert!(!ids.contains(&battery_id.as_str()));
+
+    // The delete is persisted, a restarted store only sees the remaining reviews
+    let reopened = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    assert_eq!(reopened.get_stats().await.total_reviews, 2);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_store_with_fake_embedder() -> Result<()> {
+    let data_dir = temp_data_dir("vector-store-test");
+    let mut store = VectorStore::new(&data_dir, HashEmbedder::new(32)).await?;
+
+    for (title, body) in [("Great sound", "Bass is deep and clear"), ("Broken hinge", "The lid snapped off")] {
+        store.insert_review(crate::models::InsertReviewRequest {
+            review_title: title.to_string(),
+            review_body: body.to_string(),
+            product_id: "HEADSET-2".to_string(),
+            review_rating: 4,
+        }).await?;
+    }
+
+    let results = store.search("lid snapped", 1).await?;
+    assert_eq!(results[0].0.review_title, "Broken hinge");
+    assert_eq!(store.get_stats(), (2, 2));
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_chunk_body_windows_overlap() {
+    let body = "One. Two!  Three?! Four\nFive... Six.";
+    let sentences: Vec<&str> = split_sentences(body).iter().map(|span| &body[span.start..span.end]).collect();
+    assert_eq!(sentences, vec!["One.", "Two!", "Three?!", "Four", "Five...", "Six."]);
+
+    let config = ChunkConfig { window_sentences: 3, overlap_sentences: 1 };
+    let chunks: Vec<&str> = chunk_body(body, &config).iter().map(|span| &body[span.start..span.end]).collect();
+    assert_eq!(chunks, vec!["One. Two!  Three?!", "Three?! Four\nFive...", "Five... Six."]);
+
+    // Short bodies stay a single chunk covering the whole text
+    let short = chunk_body("Works fine. ", &config);
+    assert_eq!((short.len(), short[0].start, short[0].end), (1, 0, "Works fine. ".len()));
+
+    // Blank bodies get no chunk to embed
+    assert!(chunk_body("", &config).is_empty());
+    assert!(chunk_body(" \n\t ", &config).is_empty());
+}
+
+#[tokio::test]
+async fn test_search_matches_chunks_of_long_reviews() -> Result<()> {
+    let data_dir = temp_data_dir("chunk-search-test");
+    let config = StoreConfig {
+        chunking: ChunkConfig { window_sentences: 2, overlap_sentences: 0 },
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let body = "Setup was quick and painless. The manual is clear. \
+                Shipping took a week. The box was dented. \
+                After a month the hinge cracked completely.";
+    let (_, long) = send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Mixed feelings",
+        "review_body": body,
+        "product_id": "LAPTOP-3",
+        "review_rating": 3
+    }))).await?;
+    send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Fine",
+        "review_body": "Setup was quick.",
+        "product_id": "LAPTOP-3",
+        "review_rating": 4
+    }))).await?;
+
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    assert_eq!(stats["data"]["total_reviews"], 2);
+    // Three body chunks plus a title vector, and one chunk plus a title
+    assert_eq!(stats["data"]["total_vectors"], 6);
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "hinge cracked after a month",
+        "limit": 1
+    }))).await?;
+    let hit = &results["data"]["reviews"][0];
+    assert_eq!(hit["review"]["id"], long["data"]["id"]);
+
+    // The best chunk is the last sentence window
+    let best = &hit["matched_chunks"][0];
+    let (start, end) = (best["start"].as_u64().unwrap() as usize, best["end"].as_u64().unwrap() as usize);
+    assert_eq!(&body[start..end], "After a month the hinge cracked completely.");
+    assert_eq!(hit["field_scores"]["body"], best["score"]);
+
+    // Sum-of-top-n scores a review by its n best chunks
+    let (_, summed) = send(&app, "POST", "/search", Some(json!({
+        "query": "hinge cracked after a month",
+        "limit": 1,
+        "chunk_aggregation": { "method": "sum_top_n", "n": 2 }
+    }))).await?;
+    let hit = &summed["data"]["reviews"][0];
+    let top_two: f64 = hit["matched_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .take(2)
+        .map(|chunk| chunk["score"].as_f64().unwrap())
+        .sum();
+    assert!((hit["field_scores"]["body"].as_f64().unwrap() - top_two).abs() < 1e-5);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_field_weights_fuse_title_and_body_scores() -> Result<()> {
+    let data_dir = temp_data_dir("field-weights-test");
+    let app = test_router(&data_dir).await?;
+
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        {
+            "review_title": "Terrible!",
+            "review_body": "Actually the camera takes sharp photos in daylight",
+            "product_id": "CAM-1",
+            "review_rating": 4
+        },
+        {
+            "review_title": "Camera review",
+            "review_body": "Terrible autofocus, terrible battery, terrible app",
+            "product_id": "CAM-1",
+            "review_rating": 1
+        }
+    ]))).await?;
+
+    let top = |weights: Value| {
+        let app = app.clone();
+        async move {
+            let (_, results) = send(&app, "POST", "/search", Some(json!({
+                "query": "terrible",
+                "field_weights": weights
+            }))).await?;
+            Ok::<_, anyhow::Error>(results["data"]["reviews"][0].clone())
+        }
+    };
+
+    let by_title = top(json!({ "title": 1.0, "body": 0.0 })).await?;
+    assert_eq!(by_title["review"]["review_title"], "Terrible!");
+    assert_eq!(by_title["similarity_score"], by_title["field_scores"]["title"]);
+
+    let by_body = top(json!({ "title": 0.0, "body": 1.0 })).await?;
+    assert_eq!(by_body["review"]["review_title"], "Camera review");
+
+    // Weights are normalized, so scaling both leaves the score unchanged
+    let balanced = top(json!({ "title": 0.3, "body": 0.7 })).await?;
+    let scaled = top(json!({ "title": 3.0, "body": 7.0 })).await?;
+    let difference = balanced["similarity_score"].as_f64().unwrap() - scaled["similarity_score"].as_f64().unwrap();
+    assert!(difference.abs() < 1e-5);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_detect_and_normalize_language() {
+    assert_eq!(
+        detect_language("The battery lasts all day and the screen is bright").as_deref(),
+        Some("eng")
+    );
+    assert_eq!(
+        detect_language("แบตเตอรี่ใช้งานได้ทั้งวัน หน้าจอสว่างมาก").as_deref(),
+        Some("tha")
+    );
+    assert_eq!(detect_language(""), None);
+
+    assert_eq!(normalize_language("THA").as_deref(), Some("tha"));
+    assert_eq!(normalize_language("thai").as_deref(), Some("tha"));
+    assert_eq!(normalize_language("klingon"), None);
+}
+
+#[tokio::test]
+async fn test_languages_filter_through_router() -> Result<()> {
+    let data_dir = temp_data_dir("languages-test");
+    let app = test_router(&data_dir).await?;
+
+    let (_, inserted) = send(&app, "POST", "/reviews/bulk", Some(json!([
+        {
+            "review_title": "Battery lasts all day",
+            "review_body": "I charge it once in the morning and it is still fine at night",
+            "product_id": "PHONE-1",
+            "review_rating": 5
+        },
+        {
+            "review_title": "แบตเตอรี่ดีมาก",
+            "review_body": "ชาร์จตอนเช้าครั้งเดียว ใช้ได้ถึงกลางคืน",
+            "product_id": "PHONE-1",
+            "review_rating": 5
+        }
+    ]))).await?;
+    assert_eq!(inserted["data"][0]["review"]["language"], "eng");
+    assert_eq!(inserted["data"][1]["review"]["language"], "tha");
+
+    // The fake embedder shares no words between the two, so the Thai review
+    // only comes back through the filtered fallback scan
+    let (status, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery lasts all day",
+        "languages": ["Thai"]
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let reviews = results["data"]["reviews"].as_array().unwrap();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0]["review"]["language"], "tha");
+
+    let (status, _) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery",
+        "languages": ["klingon"]
+    }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+/// English reviews paired with a Thai query that should find each of them.
+const CROSS_LINGUAL_CASES: &[(&str, &str, &str)] = &[
+    ("Battery life is amazing", "The battery easily lasts two full days on a single charge", "แบตเตอรี่อยู่ได้นาน"),
+    ("Screen cracked", "The screen cracked after I dropped it from a small height", "หน้าจอแตก"),
+    ("Late delivery", "The package arrived three weeks later than promised", "ส่งของช้ามาก"),
+    ("Too loud", "The fan is extremely noisy even when the laptop is idle", "พัดลมเสียงดัง"),
+    ("Perfect fit", "These shoes fit perfectly and are comfortable for long walks", "รองเท้าใส่สบาย"),
+];
+
+#[tokio::test]
+async fn test_fastembed_cross_lingual_recall() -> Result<()> {
+    let model = ModelSource::Builtin(EmbeddingModel::MultilingualE5Small);
+    assert_eq!(model.default_prefixes(), TextPrefixes::e5());
+
+    let service = FastEmbedService::new(EmbeddingConfig {
+        prefixes: model.default_prefixes(),
+        model,
+        ..EmbeddingConfig::default()
+    })?;
+
+    let data_dir = temp_data_dir("cross-lingual-test");
+    let store = SPFreshVectorStore::new(&data_dir, service, StoreConfig::default()).await?;
+    for (title, body, _) in CROSS_LINGUAL_CASES {
+        store.insert_review(crate::models::InsertReviewRequest {
+            review_title: title.to_string(),
+            review_body: body.to_string(),
+            product_id: "MIXED".to_string(),
+            review_rating: 3,
+        }).await?;
+    }
+
+    let mut found = 0;
+    for (title, _, thai_query) in CROSS_LINGUAL_CASES {
+        let results = store.search(&SearchRequest {
+            query: thai_query.to_string(),
+            limit: Some(1),
+            filters: ReviewFilters {
+                languages: Some(vec!["eng".to_string()]),
+                ..ReviewFilters::default()
+            },
+            ..SearchRequest::default()
+        }).await?;
+        if results.first().is_some_and(|hit| hit.review.review_title == *title) {
+            found += 1;
+        }
+    }
+    assert!(found >= 4, "Thai queries found only {} of {} English reviews", found, CROSS_LINGUAL_CASES.len());
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_explain_reports_scores_sources_and_filters() -> Result<()> {
+    let data_dir = temp_data_dir("explain-test");
+    let app = test_router(&data_dir).await?;
+
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        {
+            "review_title": "Battery lasts all day",
+            "review_body": "I charge the battery once in the morning and it is still fine at night",
+            "product_id": "PHONE-1",
+            "review_rating": 5
+        },
+        {
+            "review_title": "แบตเตอรี่ดีมาก",
+            "review_body": "ชาร์จตอนเช้าครั้งเดียว ใช้ได้ถึงกลางคืน",
+            "product_id": "PHONE-1",
+            "review_rating": 5
+        },
+        {
+            "review_title": "Battery is fine",
+            "review_body": "The battery lasts a day",
+            "product_id": "PHONE-2",
+            "review_rating": 4
+        },
+        {
+            "review_title": "All day battery",
+            "review_body": "Lasts all day with heavy use",
+            "product_id": "PHONE-3",
+            "review_rating": 5
+        }
+    ]))).await?;
+
+    let (status, explained) = send(&app, "POST", "/search/explain", Some(json!({
+        "query": "battery lasts all day"
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let data = &explained["data"];
+    assert_eq!(data["fallback_scan"], false);
+    assert_eq!(data["ann_candidates"], 8);
+
+    let top = &data["reviews"][0];
+    assert_eq!(top["review"]["language"], "eng");
+    assert_eq!(top["source"], "ann");
+    assert!(top["filters"].as_array().unwrap().is_empty());
+
+    // Index candidates are scored with exact cosines, so the search score is
+    // the exact one
+    for vector in top["vectors"].as_array().unwrap() {
+        assert!(vector["ann_distance"].is_number());
+    }
+    assert!(top["ann_distance"].is_number());
+    let exact_score = top["exact_score"].as_f64().unwrap();
+    assert!((exact_score - top["similarity_score"].as_f64().unwrap()).abs() < 1e-4);
+
+    // With one result requested the index only returns the English reviews'
+    // vectors; filtering to Thai rejects them and falls back to a scan
+    let (_, explained) = send(&app, "POST", "/search/explain", Some(json!({
+        "query": "battery lasts all day",
+        "limit": 1,
+        "languages": ["tha"]
+    }))).await?;
+    let data = &explained["data"];
+    assert_eq!(data["fallback_scan"], true);
+    assert_eq!(data["reviews"][0]["source"], "fallback_scan");
+    assert_eq!(data["reviews"][0]["filters"][0], json!({ "filter": "languages", "value": "tha", "passed": true }));
+    assert_eq!(data["rejected"][0]["filters"][0]["value"], "eng");
+    assert_eq!(data["rejected"][0]["filters"][0]["passed"], false);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_diversity_reranking_suppresses_duplicates() -> Result<()> {
+    let data_dir = temp_data_dir("diversity-test");
+    let app = test_router(&data_dir).await?;
+
+    let spam = |product_id: &str| json!({
+        "review_title": "Best headphones ever",
+        "review_body": "Best headphones ever, great sound, buy now at my shop",
+        "product_id": product_id,
+        "review_rating": 5
+    });
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        spam("HP-1"),
+        spam("HP-2"),
+        spam("HP-3"),
+        spam("HP-4"),
+        {
+            "review_title": "Great sound for the price",
+            "review_body": "The headphones have clear highs and deep bass",
+            "product_id": "HP-1",
+            "review_rating": 4
+        },
+        {
+            "review_title": "Comfortable headphones",
+            "review_body": "I wear them all day, sound is great",
+            "product_id": "HP-2",
+            "review_rating": 5
+        }
+    ]))).await?;
+
+    let spam_in_top_three = |results: &Value| {
+        results["data"]["reviews"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|hit| hit["review"]["review_title"] == "Best headphones ever")
+            .count()
+    };
+
+    let query = "great sound headphones";
+    let (_, plain) = send(&app, "POST", "/search", Some(json!({ "query": query, "limit": 3 }))).await?;
+    assert_eq!(spam_in_top_three(&plain), 3);
+
+    let (_, diverse) = send(&app, "POST", "/search", Some(json!({
+        "query": query,
+        "limit": 3,
+        "diversity": 0.7
+    }))).await?;
+    assert_eq!(diverse["data"]["reviews"].as_array().unwrap().len(), 3);
+    assert_eq!(spam_in_top_three(&diverse), 1);
+
+    let (status, _) = send(&app, "POST", "/search", Some(json!({ "query": query, "diversity": 1.5 }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_group_search_results_by_product() -> Result<()> {
+    let data_dir = temp_data_dir("group-by-test");
+    let app = test_router(&data_dir).await?;
+
+    let review = |title: &str, body: &str, product_id: &str, rating: u8| json!({
+        "review_title": title,
+        "review_body": body,
+        "product_id": product_id,
+        "review_rating": rating
+    });
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        review("Battery drains fast", "The battery drains fast, battery is dead by noon", "PHONE-A", 1),
+        review("Battery complaint", "Battery drains fast when the screen is on", "PHONE-A", 2),
+        review("Battery could be better", "Battery drains a bit fast", "PHONE-A", 3),
+        review("Decent phone", "The battery drains overnight", "PHONE-B", 2),
+        review("Nice case", "Soft grip and a nice color", "CASE-C", 5)
+    ]))).await?;
+
+    let (status, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery drains fast",
+        "group_by": "product_id",
+        "groups": 2,
+        "per_group": 2
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    let data = &results["data"];
+    assert!(data["reviews"].as_array().unwrap().is_empty());
+    let groups = data["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+
+    assert_eq!(groups[0]["key"], "PHONE-A");
+    assert_eq!(groups[0]["matching_reviews"], 3);
+    assert_eq!(groups[0]["mean_rating"], 2.0);
+    assert_eq!(groups[0]["reviews"].as_array().unwrap().len(), 2);
+    assert_eq!(groups[0]["best_score"], groups[0]["reviews"][0]["similarity_score"]);
+    assert_eq!(groups[1]["key"], "PHONE-B");
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recency_and_rating_boosts() -> Result<()> {
+    let data_dir = temp_data_dir("ranking-test");
+    std::fs::create_dir_all(&data_dir)?;
+
+    // Same complaint written years apart, with different ratings
+    let now = chrono::Utc::now();
+    let stored = [
+        ("old", (now - chrono::Duration::days(2000)).to_rfc3339(), 1),
+        ("new", (now - chrono::Duration::days(7)).to_rfc3339(), 4),
+    ];
+    let lines: Vec<String> = stored
+        .iter()
+        .map(|(id, timestamp, rating)| json!({
+            "id": id,
+            "review_title": "Charger stopped working",
+            "review_body": "The charger stopped working after a month",
+            "product_id": "CHARGER-1",
+            "review_rating": rating,
+            "timestamp": timestamp
+        }).to_string())
+        .collect();
+    std::fs::write(format!("{}/reviews.jsonl", data_dir), lines.join("\n") + "\n")?;
+    let app = test_router(&data_dir).await?;
+
+    let search = |ranking: Value| {
+        let app = app.clone();
+        async move {
+            let (_, results) = send(&app, "POST", "/search", Some(json!({
+                "query": "charger stopped working",
+                "ranking": ranking
+            }))).await?;
+            Ok::<_, anyhow::Error>(results["data"]["reviews"].clone())
+        }
+    };
+
+    let recent_first = search(json!({ "recency_weight": 1.0, "recency_half_life_days": 30 })).await?;
+    assert_eq!(recent_first[0]["review"]["id"], "new");
+    let breakdown = &recent_first[0]["score_breakdown"];
+    let recency = breakdown["recency_boost"].as_f64().unwrap();
+    assert!((recency - 0.5f64.powf(7.0 / 30.0)).abs() < 1e-3);
+    assert_eq!(breakdown["rating_boost"], 0.5);
+    let expected = breakdown["semantic"].as_f64().unwrap() + recency;
+    assert!((breakdown["final_score"].as_f64().unwrap() - expected).abs() < 1e-5);
+    assert_eq!(recent_first[0]["similarity_score"], breakdown["final_score"]);
+
+    // A negative rating weight surfaces the one-star review instead
+    let complaints_first = search(json!({ "rating_weight": -0.5 })).await?;
+    assert_eq!(complaints_first[0]["review"]["id"], "old");
+    assert_eq!(complaints_first[0]["score_breakdown"]["rating_boost"], -1.0);
+
+    let (status, _) = send(&app, "POST", "/search", Some(json!({
+        "query": "charger",
+        "ranking": { "recency_half_life_days": 0 }
+    }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+/// Starts a webhook receiver on a free local port that answers 500 to the
+/// first `failures` requests and records every payload it gets.
+async fn webhook_listener(failures: usize) -> Result<(String, Arc<std::sync::Mutex<Vec<Value>>>)> {
+    let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let app = Router::new().route(
+        "/hook",
+        axum::routing::post({
+            let received = Arc::clone(&received);
+            move |axum::Json(payload): axum::Json<Value>| async move {
+                let mut received = received.lock().unwrap();
+                received.push(payload);
+                if received.len() <= failures {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                } else {
+                    StatusCode::OK
+                }
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/hook", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    Ok((url, received))
+}
+
+#[tokio::test]
+async fn test_alerts_percolate_new_reviews_and_retry_webhooks() -> Result<()> {
+    let data_dir = temp_data_dir("alerts-test");
+    let app = test_router(&data_dir).await?;
+    let (webhook_url, received) = webhook_listener(1).await?;
+
+    let (status, alert) = send(&app, "POST", "/alerts", Some(json!({
+        "query": "battery caught fire",
+        "threshold": 0.4,
+        "webhook_url": webhook_url
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let alert_id = alert["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(&app, "POST", "/alerts", Some(json!({ "query": "fire", "threshold": 2.0 }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        {
+            "review_title": "Dangerous",
+            "review_body": "The battery caught fire while charging",
+            "product_id": "PHONE-1",
+            "review_rating": 1
+        },
+        {
+            "review_title": "Lovely colors",
+            "review_body": "The case looks great",
+            "product_id": "CASE-1",
+            "review_rating": 5
+        }
+    ]))).await?;
+
+    let (_, matches) = send(&app, "GET", &format!("/alerts/{}/matches", alert_id), None).await?;
+    let matches = matches["data"].as_array().unwrap().clone();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["review"]["review_title"], "Dangerous");
+    assert!(std::path::Path::new(&data_dir).join("alerts/matches.jsonl").exists());
+
+    // The first delivery fails and is retried after the backoff
+    for _ in 0..50 {
+        if received.lock().unwrap().len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let payloads = received.lock().unwrap().clone();
+    assert_eq!(payloads.len(), 2);
+    assert_eq!(payloads[1]["id"], matches[0]["id"]);
+    assert_eq!(payloads[1]["alert_id"], alert_id.as_str());
+
+    let deliveries_path = std::path::Path::new(&data_dir).join("alerts/deliveries.jsonl");
+    for _ in 0..50 {
+        if deliveries_path.exists() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let delivery: Value = serde_json::from_str(std::fs::read_to_string(&deliveries_path)?.trim())?;
+    assert_eq!(delivery["delivered"], true);
+    assert_eq!(delivery["attempts"], 2);
+
+    // Alerts survive a restart
+    let reopened = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    assert_eq!(reopened.list_alerts().await.len(), 1);
+
+    let (status, _) = send(&app, "DELETE", &format!("/alerts/{}", alert_id), None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &format!("/alerts/{}/matches", alert_id), None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+/// Reads `count` server-sent events from an `/events` response body as
+/// `(id, event, data)`.
+async fn read_events(body: &mut axum::body::BodyDataStream, count: usize) -> Result<Vec<(String, String, Value)>> {
+    use futures_util::StreamExt;
+
+    let mut buffer = String::new();
+    let mut events = Vec::new();
+    while events.len() < count {
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), body.next())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("event stream ended"))??;
+        buffer.push_str(std::str::from_utf8(&chunk)?);
+
+        while let Some(end) = buffer.find("\n\n") {
+            let frame: String = buffer.drain(..end + 2).collect();
+            let (mut id, mut event, mut data) = (None, None, None);
+            for line in frame.lines() {
+                if let Some(value) = line.strip_prefix("id: ") {
+                    id = Some(value.to_string());
+                } else if let Some(value) = line.strip_prefix("event: ") {
+                    event = Some(value.to_string());
+                } else if let Some(value) = line.strip_prefix("data: ") {
+                    data = Some(serde_json::from_str(value)?);
+                }
+            }
+            // Keep-alive comments carry no id
+            if let (Some(id), Some(event), Some(data)) = (id, event, data) {
+                events.push((id, event, data));
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+async fn open_events(app: &Router, last_event_id: Option<&str>) -> Result<axum::body::BodyDataStream> {
+    let mut request = Request::builder().method("GET").uri("/events");
+    if let Some(id) = last_event_id {
+        request = request.header("last-event-id", id);
+    }
+
+    let response = app.clone().oneshot(request.body(Body::empty())?).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    Ok(response.into_body().into_data_stream())
+}
+
+#[tokio::test]
+async fn test_event_stream_reports_writes_and_resumes() -> Result<()> {
+    let data_dir = temp_data_dir("events-test");
+    let app = test_router(&data_dir).await?;
+    let mut stream = open_events(&app, None).await?;
+
+    send(&app, "POST", "/alerts", Some(json!({ "query": "battery caught fire", "threshold": 0.4 }))).await?;
+    let (_, inserted) = send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Dangerous",
+        "review_body": "The battery caught fire while charging",
+        "product_id": "PHONE-1",
+        "review_rating": 1
+    }))).await?;
+    let review_id = inserted["data"]["id"].as_str().unwrap().to_string();
+    send(&app, "DELETE", &format!("/reviews/{}", review_id), None).await?;
+
+    let events = read_events(&mut stream, 3).await?;
+    let kinds: Vec<&str> = events.iter().map(|(_, kind, _)| kind.as_str()).collect();
+    assert_eq!(kinds, ["review.inserted", "alert.matched", "review.deleted"]);
+    let (epoch, first_seq) = events[0].0.split_once('-').unwrap();
+    assert_eq!(first_seq, "1");
+    let ids: Vec<String> = (1..=3).map(|seq| format!("{}-{}", epoch, seq)).collect();
+    assert_eq!(events.iter().map(|(id, _, _)| id.clone()).collect::<Vec<_>>(), ids);
+    assert_eq!(events[0].2["id"], review_id.as_str());
+    assert_eq!(events[1].2["review"]["id"], review_id.as_str());
+    assert_eq!(events[2].2["id"], review_id.as_str());
+
+    // A client that saw event 1 gets the rest replayed, then live events
+    let mut resumed = open_events(&app, Some(&ids[0])).await?;
+    let replayed = read_events(&mut resumed, 2).await?;
+    assert_eq!(replayed.iter().map(|(id, _, _)| id.clone()).collect::<Vec<_>>(), ids[1..]);
+
+    send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Lovely colors",
+        "review_body": "The case looks great",
+        "product_id": "CASE-1",
+        "review_rating": 5
+    }))).await?;
+    let live = read_events(&mut resumed, 1).await?;
+    assert_eq!((live[0].0.as_str(), live[0].1.as_str()), (format!("{}-4", epoch).as_str(), "review.inserted"));
+
+    // Ids from another run of the server can't be replayed: the client is
+    // told to resync and given the current position instead
+    for stale in ["0-2", "2"] {
+        let mut restarted = open_events(&app, Some(stale)).await?;
+        let resync = read_events(&mut restarted, 1).await?;
+        assert_eq!((resync[0].0.as_str(), resync[0].1.as_str()), (format!("{}-4", epoch).as_str(), "resync"));
+    }
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cluster_reviews_by_topic() -> Result<()> {
+    let data_dir = temp_data_dir("clusters-test");
+    let app = test_router(&data_dir).await?;
+
+    let reviews: Vec<Value> = [
+        ("Battery drains fast", "The battery drains overnight and needs charging twice a day", "PHONE-1"),
+        ("Weak battery", "Battery barely lasts, charging all the time", "PHONE-1"),
+        ("Battery died", "After a week the battery drains even when idle, constant charging", "PHONE-2"),
+        ("Late delivery", "Shipping took three weeks and the parcel box arrived crushed", "PHONE-1"),
+        ("Slow shipping", "The parcel shipping was slow, box damaged on arrival", "PHONE-2"),
+        ("Courier trouble", "Shipping courier lost the parcel, box finally arrived late", "PHONE-2"),
+    ]
+    .iter()
+    .map(|(title, body, product)| json!({
+        "review_title": title,
+        "review_body": body,
+        "product_id": product,
+        "review_rating": 2
+    }))
+    .collect();
+    send(&app, "POST", "/reviews/bulk", Some(Value::Array(reviews))).await?;
+
+    let (status, _) = send(&app, "GET", "/analytics/clusters", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "POST", "/analytics/clusters", Some(json!({ "since": "last tuesday" }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for method in ["kmeans", "balanced"] {
+        let (status, run) = send(&app, "POST", "/analytics/clusters", Some(json!({ "k": 2, "method": method }))).await?;
+        assert_eq!(status, StatusCode::OK);
+        let run = &run["data"];
+        assert_eq!(run["reviews_clustered"], 6);
+
+        let clusters = run["clusters"].as_array().unwrap();
+        assert_eq!(clusters.len(), 2);
+        let battery = clusters
+            .iter()
+            .find(|cluster| cluster["top_terms"].as_array().unwrap().contains(&json!("battery")))
+            .expect("a battery cluster");
+        assert_eq!(battery["size"], 3);
+        assert_eq!(battery["central_reviews"].as_array().unwrap().len(), 3);
+        assert!(battery["label"].as_str().unwrap().contains("battery"));
+        let shipping = clusters.iter().find(|cluster| cluster["cluster_id"] != battery["cluster_id"]).unwrap();
+        assert!(shipping["top_terms"].as_array().unwrap().contains(&json!("shipping")));
+    }
+
+    // Search results carry the cluster of the latest run
+    let (_, latest) = send(&app, "GET", "/analytics/clusters", None).await?;
+    let battery_id = latest["data"]["clusters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|cluster| cluster["top_terms"].as_array().unwrap().contains(&json!("battery")))
+        .unwrap()["cluster_id"]
+        .clone();
+    let (_, search) = send(&app, "POST", "/search", Some(json!({ "query": "battery charging", "limit": 1 }))).await?;
+    assert_eq!(search["data"]["reviews"][0]["cluster_id"], battery_id);
+
+    // Scoped runs only cluster matching reviews and replace the assignments
+    let (_, run) = send(&app, "POST", "/analytics/clusters", Some(json!({ "k": 1, "product_ids": ["PHONE-2"] }))).await?;
+    assert_eq!(run["data"]["reviews_clustered"], 3);
+    let (_, search) = send(&app, "POST", "/search", Some(json!({ "query": "Weak battery", "limit": 1 }))).await?;
+    assert_eq!(search["data"]["reviews"][0]["review"]["product_id"], "PHONE-1");
+    assert!(search["data"]["reviews"][0].get("cluster_id").is_none());
+
+    // Assignments persist across restarts
+    let reopened = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    assert_eq!(reopened.latest_clusters().await.unwrap().reviews_clustered, 3);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_sentiment_lexicon_and_negation() -> Result<()> {
+    let analyzer = SentimentAnalyzer::default();
+
+    assert_eq!(analyzer.score("Great screen, excellent battery"), 1.0);
+    assert_eq!(analyzer.score("Terrible. Broken on arrival"), -1.0);
+    assert_eq!(analyzer.score("Arrived on Tuesday"), 0.0);
+    assert_eq!(analyzer.score("Great screen but awful speakers"), 0.0);
+
+    // Negations flip the next few words of their clause only
+    assert_eq!(analyzer.score("Not good at all"), -1.0);
+    assert_eq!(analyzer.score("It doesn’t feel cheap"), 1.0);
+    assert_eq!(analyzer.score("Not what I expected, great value"), 1.0);
+    assert_eq!(analyzer.score("No issues with it whatsoever, still lovely"), 1.0);
+
+    let data_dir = temp_data_dir("sentiment-lexicon");
+    std::fs::create_dir_all(&data_dir)?;
+    let path = std::path::Path::new(&data_dir).join("lexicon.json");
+    std::fs::write(&path, r#"{"positive": ["sturdy"], "negative": ["Wobbly"]}"#)?;
+    let custom = SentimentAnalyzer::load(&path)?;
+    assert_eq!(custom.score("Sturdy frame"), 1.0);
+    assert_eq!(custom.score("wobbly legs, not sturdy"), -1.0);
+    assert_eq!(custom.score("great"), 0.0);
+    // Negations keep their defaults
+    assert_eq!(custom.score("never wobbly"), 1.0);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sentiment_filter_and_rating_mismatch_stats() -> Result<()> {
+    let data_dir = temp_data_dir("sentiment-test");
+    let app = test_router(&data_dir).await?;
+
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        { "review_title": "Great battery", "review_body": "Excellent battery, lasts all week", "product_id": "P1", "review_rating": 5 },
+        { "review_title": "Battery is bad", "review_body": "Terrible battery, not good", "product_id": "P1", "review_rating": 1 },
+        { "review_title": "Battery", "review_body": "Love the battery, perfect", "product_id": "P1", "review_rating": 1 },
+        { "review_title": "Battery", "review_body": "Awful battery, returned it", "product_id": "P1", "review_rating": 4 }
+    ]))).await?;
+
+    let stored = std::fs::read_to_string(std::path::Path::new(&data_dir).join("reviews.jsonl"))?;
+    let first: Value = serde_json::from_str(stored.lines().next().unwrap())?;
+    assert_eq!(first["sentiment"], 1.0);
+
+    let (status, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery",
+        "sentiment": { "max": -0.25 }
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    let titles: Vec<&str> = results["data"]["reviews"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["review"]["review_title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles.len(), 2);
+    assert!(titles.contains(&"Battery is bad"));
+
+    let (status, _) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery",
+        "sentiment": { "min": 0.5, "max": 0.0 }
+    }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    let sentiment = &stats["data"]["sentiment"];
+    assert_eq!(sentiment["positive_text_low_rating"], 1);
+    assert_eq!(sentiment["negative_text_high_rating"], 1);
+    let one_star = &sentiment["by_rating"][0];
+    assert_eq!((one_star["rating"].as_u64(), one_star["reviews"].as_u64()), (Some(1), Some(2)));
+    assert_eq!((one_star["positive"].as_u64(), one_star["negative"].as_u64()), (Some(1), Some(1)));
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_zero_shot_labels_filters_and_facets() -> Result<()> {
+    let data_dir = temp_data_dir("labels-test");
+    let app = test_router(&data_dir).await?;
+
+    let (status, _) = send(&app, "POST", "/labels", Some(json!({ "name": " ", "prototypes": ["x"] }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", "/labels", Some(json!({ "name": "shipping", "prototypes": [] }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, label) = send(&app, "POST", "/labels", Some(json!({
+        "name": "shipping",
+        "prototypes": ["shipping was slow", "the parcel arrived late"],
+        "threshold": 0.5
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(label["data"]["threshold"], 0.5);
+    send(&app, "POST", "/labels", Some(json!({
+        "name": "defect",
+        "prototypes": ["it stopped working", "arrived broken"],
+        "threshold": 0.5
+    }))).await?;
+
+    let (_, inserted) = send(&app, "POST", "/reviews/bulk", Some(json!([
+        { "review_title": "Slow", "review_body": "Shipping was slow and the parcel arrived late", "product_id": "P1", "review_rating": 2 },
+        { "review_title": "Dead", "review_body": "It stopped working after a day", "product_id": "P1", "review_rating": 1 },
+        { "review_title": "Pricey", "review_body": "Way too expensive for what it is", "product_id": "P2", "review_rating": 2 }
+    ]))).await?;
+    let labels: Vec<Value> = inserted["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["review"].get("labels").cloned().unwrap_or(json!([])))
+        .collect();
+    assert_eq!(labels, [json!(["shipping"]), json!(["defect"]), json!([])]);
+
+    // New labels apply to stored reviews once backfilled
+    send(&app, "POST", "/labels", Some(json!({
+        "name": "pricing",
+        "prototypes": ["too expensive for what it is"],
+        "threshold": 0.5
+    }))).await?;
+    let (status, report) = send(&app, "POST", "/labels/backfill", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["data"]["reviews"], 3);
+    assert_eq!(report["data"]["labeled_reviews"], 3);
+    assert_eq!(report["data"]["counts"], json!({ "shipping": 1, "defect": 1, "pricing": 1 }));
+    let stored = std::fs::read_to_string(std::path::Path::new(&data_dir).join("reviews.jsonl"))?;
+    assert!(stored.lines().any(|line| line.contains(r#""labels":["pricing"]"#)));
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "arrived",
+        "labels": ["pricing", "defect"],
+        "facets": ["labels"]
+    }))).await?;
+    let titles: Vec<&str> = results["data"]["reviews"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["review"]["review_title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles.len(), 2);
+    assert!(!titles.contains(&"Slow"));
+    assert_eq!(results["data"]["facets"]["labels"], json!([
+        { "value": "defect", "count": 1 },
+        { "value": "pricing", "count": 1 }
+    ]));
+
+    // Facets count every candidate, not just the page
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "slow", "limit": 2, "facets": ["labels"] }))).await?;
+    assert_eq!(results["data"]["reviews"].as_array().unwrap().len(), 2);
+    let facet_total: u64 = results["data"]["facets"]["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| bucket["count"].as_u64().unwrap())
+        .sum();
+    assert_eq!(facet_total, 3);
+
+    let (status, _) = send(&app, "DELETE", "/labels/pricing", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", "/labels/pricing", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, labels) = send(&app, "GET", "/labels", None).await?;
+    assert_eq!(labels["data"].as_array().unwrap().len(), 2);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_product_aspect_sentiment() -> Result<()> {
+    let text = "Love the screen but hate the battery. Fast delivery; great price";
+    let clauses: Vec<&str> = split_clauses(text).iter().map(|span| &text[span.start..span.end]).collect();
+    assert_eq!(clauses, ["Love the screen", "but hate the battery.", "Fast delivery", "great price"]);
+
+    assert!(parse_aspects("battery,battery=charging").is_err());
+    assert!(parse_aspects(" , ").is_err());
+
+    let data_dir = temp_data_dir("aspects-test");
+    let config = StoreConfig {
+        aspects: AspectConfig {
+            aspects: parse_aspects("battery,screen,price=price value")?,
+            threshold: 0.4,
+        },
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    send(&app, "POST", "/reviews/bulk", Some(json!([
+        { "review_title": "Mixed", "review_body": "Love the screen but hate the battery.", "product_id": "PHONE-1", "review_rating": 3 },
+        { "review_title": "Battery", "review_body": "The battery is terrible. Screen is excellent", "product_id": "PHONE-1", "review_rating": 2 },
+        { "review_title": "Other", "review_body": "The battery is great", "product_id": "PHONE-2", "review_rating": 5 }
+    ]))).await?;
+
+    let (status, _) = send(&app, "GET", "/products/NOPE/aspects", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(&app, "GET", "/products/PHONE-1/aspects", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let result = &body["data"];
+    assert_eq!(result["reviews"], 2);
+
+    let aspects = result["aspects"].as_array().unwrap();
+    let aspect = |name: &str| aspects.iter().find(|aspect| aspect["aspect"] == name).cloned().unwrap();
+    // "price" is never mentioned
+    assert_eq!(aspects.len(), 2);
+
+    let battery = aspect("battery");
+    assert_eq!((battery["mentions"].as_u64(), battery["negative"].as_u64()), (Some(3), Some(2)));
+    assert!(battery["mean_sentiment"].as_f64().unwrap() < 0.0);
+    let snippets: Vec<&str> = battery["snippets"].as_array().unwrap().iter().map(|s| s["text"].as_str().unwrap()).collect();
+    assert!(snippets.contains(&"but hate the battery."));
+    assert!(!snippets.contains(&"The battery is great"));
+
+    let screen = aspect("screen");
+    assert_eq!((screen["mentions"].as_u64(), screen["positive"].as_u64()), (Some(2), Some(2)));
+    assert_eq!(screen["mean_sentiment"], 1.0);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_facets_count_filtered_candidates() -> Result<()> {
+    let data_dir = temp_data_dir("facets-test");
+    std::fs::create_dir_all(&data_dir)?;
+
+    let stored = [
+        ("a", "2024-01-15T10:00:00Z", "CHARGER-1", 1, "eng"),
+        ("b", "2024-01-31T23:30:00-02:00", "CHARGER-1", 1, "eng"),
+        ("c", "2024-03-02T08:00:00Z", "CHARGER-2", 5, "eng"),
+        ("d", "2024-03-09T08:00:00Z", "CHARGER-1", 2, "eng"),
+        ("e", "2024-03-10T08:00:00Z", "CHARGER-2", 1, "tha"),
+    ];
+    let lines: Vec<String> = stored
+        .iter()
+        .map(|(id, timestamp, product, rating, language)| json!({
+            "id": id,
+            "review_title": "Charger stopped working",
+            "review_body": "The charger stopped working after a month",
+            "product_id": product,
+            "review_rating": rating,
+            "timestamp": timestamp,
+            "language": language
+        }).to_string())
+        .collect();
+    std::fs::write(format!("{}/reviews.jsonl", data_dir), lines.join("\n") + "\n")?;
+    let app = test_router(&data_dir).await?;
+
+    let (status, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "charger stopped working",
+        "limit": 1,
+        "languages": ["eng"],
+        "facets": ["review_rating", "product_id", "month"]
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["data"]["reviews"].as_array().unwrap().len(), 1);
+
+    let facets = &results["data"]["facets"];
+    assert_eq!(facets["review_rating"], json!([
+        { "value": "5", "count": 1 },
+        { "value": "2", "count": 1 },
+        { "value": "1", "count": 2 }
+    ]));
+    assert_eq!(facets["product_id"], json!([
+        { "value": "CHARGER-1", "count": 3 },
+        { "value": "CHARGER-2", "count": 1 }
+    ]));
+    // Months are taken in UTC, so "b" falls into February
+    assert_eq!(facets["month"], json!([
+        { "value": "2024-01", "count": 1 },
+        { "value": "2024-02", "count": 1 },
+        { "value": "2024-03", "count": 2 }
+    ]));
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "charger" }))).await?;
+    assert!(results["data"].get("facets").is_none());
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_product_catalog_crud_join_and_filters() -> Result<()> {
+    let data_dir = temp_data_dir("catalog-test");
+    let app = test_router(&data_dir).await?;
+
+    let (status, created) = send(&app, "POST", "/products", Some(json!({
+        "id": "PHONE-1",
+        "name": "Pixel 8",
+        "category": "Phones",
+        "brand": "Google"
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["data"]["name"], "Pixel 8");
+
+    let (status, _) = send(&app, "POST", "/products", Some(json!({ "id": "PHONE-1", "name": "Other" }))).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = send(&app, "POST", "/products", Some(json!({ "id": "PHONE-2", "name": " " }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, report) = send(&app, "POST", "/products/bulk", Some(json!([
+        { "id": "PHONE-1", "name": "Pixel 8 Pro", "category": "phones", "brand": "Google" },
+        { "id": "PHONE-2", "name": "Galaxy S24", "category": "Phones", "brand": "Samsung" },
+        { "id": "TV-1", "name": "Frame TV", "category": "Televisions", "brand": "Samsung" }
+    ]))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(report["data"], json!({ "created": 2, "updated": 1 }));
+
+    let (_, phones) = send(&app, "GET", "/products?category=PHONES", None).await?;
+    let ids: Vec<&str> = phones["data"].as_array().unwrap().iter().map(|p| p["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["PHONE-1", "PHONE-2"]);
+
+    for (product, title) in [("PHONE-1", "Battery drains fast"), ("PHONE-2", "Battery drains fast"), ("TV-1", "Battery drains fast"), ("LOOSE-1", "Battery drains fast")] {
+        let (status, _) = send(&app, "POST", "/reviews", Some(json!({
+            "review_title": title,
+            "review_body": "The battery drains fast overnight",
+            "product_id": product,
+            "review_rating": 2
+        }))).await?;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains", "limit": 10 }))).await?;
+    let reviews = results["data"]["reviews"].as_array().unwrap();
+    assert_eq!(reviews.len(), 4);
+    for review in reviews {
+        match review["review"]["product_id"].as_str().unwrap() {
+            "PHONE-1" => assert_eq!(review["product"]["name"], "Pixel 8 Pro"),
+            "LOOSE-1" => assert!(review.get("product").is_none()),
+            _ => assert!(review["product"]["brand"].is_string()),
+        }
+    }
+
+    let product_ids = |results: &Value| -> Vec<String> {
+        let mut ids: Vec<String> = results["data"]["reviews"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|review| review["review"]["product_id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains", "category": "phones" }))).await?;
+    assert_eq!(product_ids(&results), ["PHONE-1", "PHONE-2"]);
+    let (_, results) = send(&app, "POST", "/search", Some(json!({
+        "query": "battery drains",
+        "category": "phones",
+        "brand": "samsung"
+    }))).await?;
+    assert_eq!(product_ids(&results), ["PHONE-2"]);
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains", "brand": "Sony" }))).await?;
+    assert!(product_ids(&results).is_empty());
+
+    let (status, updated) = send(&app, "PUT", "/products/TV-1", Some(json!({ "name": "Frame TV", "category": "Phones" }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(updated["data"].get("brand").is_none());
+    let (status, _) = send(&app, "PUT", "/products/NOPE", Some(json!({ "name": "Nope" }))).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&app, "DELETE", "/products/PHONE-2", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", "/products/PHONE-2", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The catalog survives a restart
+    let app = test_router(&data_dir).await?;
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains", "category": "phones" }))).await?;
+    assert_eq!(product_ids(&results), ["PHONE-1", "TV-1"]);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_product_summary_histogram_and_representatives() -> Result<()> {
+    let data_dir = temp_data_dir("summary-test");
+    let app = test_router(&data_dir).await?;
+
+    let reviews = [
+        ("Battery lasts all day", "battery lasts all day long", 5),
+        ("Battery lasts all week", "battery lasts all day easily", 5),
+        ("Battery lasts", "the battery lasts all day", 4),
+        ("Lovely gift box", "came wrapped in a lovely gift box", 4),
+        ("Just okay", "nothing special", 3),
+        ("Screen cracked", "screen cracked in the first week", 1),
+        ("Screen cracked again", "the screen cracked after a week", 2),
+    ];
+    for (title, body, rating) in reviews {
+        send(&app, "POST", "/reviews", Some(json!({
+            "review_title": title,
+            "review_body": body,
+            "product_id": "PHONE-1",
+            "review_rating": rating
+        }))).await?;
+    }
+    send(&app, "POST", "/reviews", Some(json!({
+        "review_title": "Other product",
+        "review_body": "battery lasts",
+        "product_id": "PHONE-2",
+        "review_rating": 5
+    }))).await?;
+    send(&app, "POST", "/products", Some(json!({ "id": "PHONE-1", "name": "Pixel 8" }))).await?;
+
+    let (status, summary) = send(&app, "GET", "/products/PHONE-1/summary?representatives=2", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let summary = &summary["data"];
+    assert_eq!(summary["reviews"], 7);
+    assert_eq!(summary["product"]["name"], "Pixel 8");
+    assert_eq!(summary["rating_histogram"], json!({ "1": 1, "2": 1, "3": 1, "4": 2, "5": 2 }));
+    assert!((summary["average_rating"].as_f64().unwrap() - 24.0 / 7.0).abs() < 1e-4);
+
+    // The gift box review is the positive outlier, never the most representative
+    let positive = summary["positive"].as_array().unwrap();
+    assert_eq!(positive.len(), 2);
+    for review in positive {
+        assert!(review["review"]["review_title"].as_str().unwrap().starts_with("Battery"));
+        assert!(review["review"]["review_rating"].as_u64().unwrap() >= 4);
+    }
+    assert!(positive[0]["similarity"].as_f64() >= positive[1]["similarity"].as_f64());
+    let negative = summary["negative"].as_array().unwrap();
+    assert_eq!(negative.len(), 2);
+    assert!(negative.iter().all(|review| review["review"]["review_rating"].as_u64().unwrap() <= 2));
+
+    let (status, _) = send(&app, "GET", "/products/NOPE/summary", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, "GET", "/products/PHONE-1/summary?representatives=0", None).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_similar_products_follow_inserts_and_deletes() -> Result<()> {
+    let data_dir = temp_data_dir("similar-products-test");
+    let app = test_router(&data_dir).await?;
+
+    let reviews = [
+        ("PHONE-1", "Battery drains fast", "the battery drains overnight"),
+        ("PHONE-1", "Battery life", "battery drains too fast"),
+        ("PHONE-2", "Battery drains", "the battery drains by noon"),
+        ("TV-1", "Shipping was late", "the box arrived two weeks late"),
+        ("TV-2", "Battery drains", "remote battery drains"),
+    ];
+    let mut review_ids = Vec::new();
+    for (product, title, body) in reviews {
+        let (_, inserted) = send(&app, "POST", "/reviews", Some(json!({
+            "review_title": title,
+            "review_body": body,
+            "product_id": product,
+            "review_rating": 2
+        }))).await?;
+        review_ids.push(inserted["data"]["id"].as_str().unwrap().to_string());
+    }
+    send(&app, "POST", "/products", Some(json!({ "id": "PHONE-2", "name": "Galaxy S24" }))).await?;
+
+    let (status, similar) = send(&app, "GET", "/products/PHONE-1/similar", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let similar = similar["data"].as_array().unwrap();
+    let ids: Vec<&str> = similar.iter().map(|product| product["product_id"].as_str().unwrap()).collect();
+    assert_eq!(ids.len(), 3);
+    assert!(!ids.contains(&"PHONE-1"));
+    assert_eq!(ids.last(), Some(&"TV-1"));
+    let phone = similar.iter().find(|product| product["product_id"] == "PHONE-2").unwrap();
+    assert_eq!(phone["product"]["name"], "Galaxy S24");
+    assert_eq!(phone["reviews"], 1);
+    assert!(similar.windows(2).all(|pair| pair[0]["similarity"].as_f64() >= pair[1]["similarity"].as_f64()));
+
+    let (_, similar) = send(&app, "GET", "/products/PHONE-1/similar?limit=1", None).await?;
+    assert_eq!(similar["data"].as_array().unwrap().len(), 1);
+
+    // Deleting a product's only review drops it from the index
+    send(&app, "DELETE", &format!("/reviews/{}", review_ids[4]), None).await?;
+    let (_, similar) = send(&app, "GET", "/products/PHONE-1/similar", None).await?;
+    let ids: Vec<&str> = similar["data"].as_array().unwrap().iter().map(|product| product["product_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["PHONE-2", "TV-1"]);
+    let (status, _) = send(&app, "GET", "/products/TV-2/similar", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Deleting one of two reviews moves the product's embedding to the other
+    let (_, before) = send(&app, "GET", "/products/PHONE-2/similar", None).await?;
+    send(&app, "DELETE", &format!("/reviews/{}", review_ids[0]), None).await?;
+    let (_, after) = send(&app, "GET", "/products/PHONE-2/similar", None).await?;
+    let similarity = |results: &Value| -> f64 {
+        results["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|product| product["product_id"] == "PHONE-1")
+            .unwrap()["similarity"]
+            .as_f64()
+            .unwrap()
+    };
+    assert_ne!(similarity(&before), similarity(&after));
+    assert_eq!(after["data"][0]["reviews"], 1);
+
+    let (status, _) = send(&app, "GET", "/products/PHONE-1/similar?limit=0", None).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_product_index_keeps_one_vector_per_product() -> Result<()> {
+    let data_dir = temp_data_dir("product-compaction-test");
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    let state = Arc::new(RwLock::new(store));
+    let app = create_router(state.clone());
+
+    // Every insert and delete supersedes its product's vector
+    let mut review_ids = Vec::new();
+    for i in 0..100 {
+        let (_, inserted) = send(&app, "POST", "/reviews", Some(json!({
+            "review_title": format!("Review {}", i),
+            "review_body": format!("battery {} drains after {} hours of use", i, i * 7),
+            "product_id": if i % 2 == 0 { "PHONE-1" } else { "PHONE-2" },
+            "review_rating": 3
+        }))).await?;
+        review_ids.push(inserted["data"]["id"].as_str().unwrap().to_string());
+    }
+    for review_id in &review_ids[..10] {
+        send(&app, "DELETE", &format!("/reviews/{}", review_id), None).await?;
+    }
+    let churned = state.read().await.product_vector_count().await;
+    assert!(churned < 110 / 2, "product index grew to {} vectors", churned);
+
+    drop(app);
+    drop(state);
+    let restarted = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), StoreConfig::default()).await?;
+    assert_eq!(restarted.product_vector_count().await, 2);
+    let app = create_router(Arc::new(RwLock::new(restarted)));
+    let (_, similar) = send(&app, "GET", "/products/PHONE-1/similar", None).await?;
+    assert_eq!(similar["data"][0]["product_id"], "PHONE-2");
+    assert_eq!(similar["data"][0]["reviews"], 45);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_near_duplicate_reviews_flagged_rejected_and_reported() -> Result<()> {
+    assert_eq!(group_pairs(&[(4, 1), (1, 7), (2, 3)]), vec![vec![1, 4, 7], vec![2, 3]]);
+    assert!(parse_duplicate_mode("maybe").is_err());
+
+    let review = |title: &str, body: &str, product: &str| json!({
+        "review_title": title,
+        "review_body": body,
+        "product_id": product,
+        "review_rating": 5
+    });
+    let original = review("Great value", "Works exactly as described and arrived fast", "PHONE-1");
+    let copy = review("great value!", "Works exactly as described, and arrived fast.", "PHONE-2");
+    let different = review("Screen cracked", "The screen cracked within a week", "PHONE-1");
+
+    // Flag mode stores the copy and points it at the original
+    let data_dir = temp_data_dir("duplicates-flag-test");
+    let config = StoreConfig {
+        duplicates: DuplicateConfig { mode: DuplicateMode::Flag, threshold: 0.95 },
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let (_, first) = send(&app, "POST", "/reviews", Some(original.clone())).await?;
+    let original_id = first["data"]["id"].as_str().unwrap().to_string();
+    assert!(first["data"].get("duplicate_of").is_none());
+    let (status, flagged) = send(&app, "POST", "/reviews", Some(copy.clone())).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(flagged["data"]["duplicate_of"], original_id.as_str());
+    let (_, unflagged) = send(&app, "POST", "/reviews", Some(different.clone())).await?;
+    assert!(unflagged["data"].get("duplicate_of").is_none());
+    send(&app, "POST", "/reviews", Some(copy.clone())).await?;
+
+    let (status, report) = send(&app, "GET", "/analytics/duplicates", None).await?;
+    assert_eq!(status, StatusCode::OK);
+    let report = &report["data"];
+    assert_eq!(report["duplicate_reviews"], 3);
+    assert_eq!(report["clusters"].as_array().unwrap().len(), 1);
+    let cluster = &report["clusters"][0];
+    assert_eq!(cluster["products"], 2);
+    assert_eq!(cluster["reviews"][0]["review_id"], original_id.as_str());
+    assert!(cluster["min_similarity"].as_f64().unwrap() >= 0.95);
+
+    let (status, _) = send(&app, "GET", "/analytics/duplicates?threshold=2", None).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    std::fs::remove_dir_all(&data_dir)?;
+
+    // Reject mode answers 409 with the duplicate's id and stores nothing
+    let data_dir = temp_data_dir("duplicates-reject-test");
+    let config = StoreConfig {
+        duplicates: DuplicateConfig { mode: DuplicateMode::Reject, threshold: 0.95 },
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let (_, first) = send(&app, "POST", "/reviews", Some(original)).await?;
+    let (status, conflict) = send(&app, "POST", "/reviews", Some(copy.clone())).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(conflict["success"], false);
+    assert_eq!(conflict["data"]["duplicate_of"], first["data"]["id"]);
+
+    let (status, bulk) = send(&app, "POST", "/reviews/bulk", Some(json!([copy, different.clone()]))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bulk["data"][0]["status"], "duplicate");
+    assert_eq!(bulk["data"][0]["duplicate_of"], first["data"]["id"]);
+    assert!(bulk["data"][0]["similarity"].as_f64().unwrap() >= 0.95);
+    assert!(bulk["data"][0].get("review").is_none());
+    assert_eq!(bulk["data"][1]["status"], "inserted");
+    assert_eq!(bulk["data"][1]["review"]["review_title"], different["review_title"]);
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    assert_eq!(stats["data"]["total_reviews"], 2);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_identical_inserts_store_one_review() -> Result<()> {
+    let data_dir = temp_data_dir("duplicates-race-test");
+    let config = StoreConfig {
+        duplicates: DuplicateConfig { mode: DuplicateMode::Reject, threshold: 0.95 },
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    // Alerts make each insert yield between embedding and storing, giving
+    // the inserts room to interleave
+    for i in 0..50 {
+        send(&app, "POST", "/alerts", Some(json!({ "query": format!("alert {}", i), "threshold": 0.9 }))).await?;
+    }
+
+    let review = json!({
+        "review_title": "Battery drains fast",
+        "review_body": "The battery drains overnight even when idle",
+        "product_id": "PHONE-1",
+        "review_rating": 2
+    });
+    let inserts: Vec<_> = (0..8)
+        .map(|_| {
+            let (app, review) = (app.clone(), review.clone());
+            tokio::spawn(async move { send(&app, "POST", "/reviews", Some(review)).await })
+        })
+        .collect();
+    let mut statuses = Vec::new();
+    for insert in inserts {
+        statuses.push(insert.await??.0);
+    }
+
+    assert_eq!(statuses.iter().filter(|status| **status == StatusCode::OK).count(), 1);
+    assert_eq!(statuses.iter().filter(|status| **status == StatusCode::CONFLICT).count(), 7);
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    assert_eq!(stats["data"]["total_reviews"], 1);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_moderation_queue_flags_and_decisions() -> Result<()> {
+    let data_dir = temp_data_dir("moderation-test");
+    let config = StoreConfig {
+        moderation: true,
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let submissions = [
+        ("Battery drains fast", "The battery drains overnight even when idle"),
+        ("WORST PHONE EVER", "DO NOT BUY THIS PHONE, IT BROKE"),
+        ("Deals", "Cheaper at https://example.com/deals today"),
+        ("Bad", "Meh"),
+        ("Same again", "the battery  drains overnight even when idle"),
+    ];
+    let mut ids = Vec::new();
+    for (title, body) in submissions {
+        let (status, inserted) = send(&app, "POST", "/reviews", Some(json!({
+            "review_title": title,
+            "review_body": body,
+            "product_id": "PHONE-1",
+            "review_rating": 1
+        }))).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(inserted["data"]["status"], "pending");
+        ids.push(inserted["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    // Pending reviews are stored but not searchable
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains" }))).await?;
+    assert!(results["data"]["reviews"].as_array().unwrap().is_empty());
+
+    let (_, queue) = send(&app, "GET", "/moderation/queue", None).await?;
+    let flags: Vec<Value> = queue["data"].as_array().unwrap().iter().map(|item| item["flags"].clone()).collect();
+    assert_eq!(flags, [
+        json!([]),
+        json!(["all_caps"]),
+        json!(["link"]),
+        json!(["short_text"]),
+        json!(["duplicate_body"]),
+    ]);
+
+    let (_, bulk) = send(&app, "POST", "/reviews/bulk", Some(json!([{
+        "review_title": "Screen cracked",
+        "review_body": "The screen cracked within a week of normal use",
+        "product_id": "PHONE-1",
+        "review_rating": 1
+    }]))).await?;
+    assert_eq!(bulk["data"][0]["status"], "pending");
+    assert_eq!(bulk["data"][0]["review"]["status"], "pending");
+
+    let (status, _) = send(&app, "POST", &format!("/moderation/{}/approve", ids[0]), Some(json!({ "moderator": " " }))).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, approved) = send(&app, "POST", &format!("/moderation/{}/approve", ids[0]), Some(json!({
+        "moderator": "mod-a"
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(approved["data"]["status"], "approved");
+    assert_eq!(approved["data"]["review"]["status"], "approved");
+    let (status, rejected) = send(&app, "POST", &format!("/moderation/{}/reject", ids[2]), Some(json!({
+        "moderator": "mod-b",
+        "note": "advertising"
+    }))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rejected["data"]["flags"], json!(["link"]));
+    let (status, _) = send(&app, "POST", &format!("/moderation/{}/reject", ids[2]), Some(json!({ "moderator": "mod-b" }))).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, results) = send(&app, "POST", "/search", Some(json!({ "query": "battery drains" }))).await?;
+    let found: Vec<&str> = results["data"]["reviews"].as_array().unwrap().iter().map(|hit| hit["review"]["id"].as_str().unwrap()).collect();
+    assert_eq!(found, [ids[0].as_str()]);
+
+    let decisions = std::fs::read_to_string(format!("{}/moderation/decisions.jsonl", data_dir))?;
+    let decisions: Vec<Value> = decisions.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+    assert_eq!(decisions.len(), 2);
+    assert_eq!(decisions[0]["moderator"], "mod-a");
+    assert_eq!(decisions[1]["status"], "rejected");
+    assert_eq!(decisions[1]["note"], "advertising");
+    assert!(decisions[1]["decided_at"].is_string());
+
+    // The queue survives a restart
+    let app = test_router(&data_dir).await?;
+    let (_, queue) = send(&app, "GET", "/moderation/queue", None).await?;
+    assert_eq!(queue["data"].as_array().unwrap().len(), 4);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pii_redaction_masks_hashes_and_rejects() -> Result<()> {
+    assert!(parse_redaction_mode("blur").is_err());
+    assert!(parse_detector("no-equals-sign").is_err());
+    assert!(RedactionConfig::with_custom(RedactionMode::Mask, vec![parse_detector("email=x")?], None).is_err());
+
+    let config = RedactionConfig::with_custom(RedactionMode::Mask, vec![parse_detector(r"order=ORD-\d{6}")?], None)?;
+    let mut fired = Vec::new();
+    let redacted = config.redact(
+        "Mail jane.doe@example.com or call +66 81 234 5678. Card 4111 1111 1111 1111, \
+         ID 1-1017-00230-70-8, order ORD-123456. Rated 5/5, model 1234567890123.",
+        &mut fired,
+    );
+    assert_eq!(
+        redacted,
+        "Mail [EMAIL] or call [PHONE]. Card [CREDIT_CARD], \
+         ID [THAI_NATIONAL_ID], order [ORDER]. Rated 5/5, model 1234567890123."
+    );
+    assert_eq!(fired, ["email", "phone", "credit_card", "thai_national_id", "order"]);
+
+    // A Thai ID that also passes the Luhn check is still reported as an ID
+    let mut fired = Vec::new();
+    assert_eq!(config.redact("ID 3-1006-01230-20-9", &mut fired), "ID [THAI_NATIONAL_ID]");
+    assert_eq!(fired, ["thai_national_id"]);
+
+    let review = json!({
+        "review_title": "Seller was helpful",
+        "review_body": "Contact me at jane.doe@example.com about the refund",
+        "product_id": "PHONE-1",
+        "review_rating": 4
+    });
+
+    // Mask mode stores and embeds the redacted text only
+    let data_dir = temp_data_dir("redaction-mask-test");
+    let config = StoreConfig {
+        redaction: RedactionConfig::with_custom(RedactionMode::Mask, Vec::new(), None)?,
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let (status, inserted) = send(&app, "POST", "/reviews", Some(review.clone())).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(inserted["data"]["review_body"], "Contact me at [EMAIL] about the refund");
+    assert_eq!(inserted["data"]["redactions"], json!(["email"]));
+    let stored = std::fs::read_to_string(format!("{}/reviews.jsonl", data_dir))?;
+    assert!(!stored.contains("jane.doe"));
+    std::fs::remove_dir_all(&data_dir)?;
+
+    // Hash mode keeps repeats of the same value recognizable, keyed by the
+    // server secret so hashes can't be recomputed without it
+    assert!(RedactionConfig::with_custom(RedactionMode::Hash, Vec::new(), None).is_err());
+    assert!(RedactionConfig::with_custom(RedactionMode::Hash, Vec::new(), Some("")).is_err());
+    let config = RedactionConfig::with_custom(RedactionMode::Hash, Vec::new(), Some("server-secret"))?;
+    assert!(!format!("{:?}", config).contains("server-secret"));
+    let hashed = config.redact("a@example.com, a@example.com, b@example.com", &mut Vec::new());
+    let tokens: Vec<&str> = hashed.split(", ").collect();
+    assert!(tokens[0].starts_with("[EMAIL:") && tokens[0].len() == "[EMAIL:]".len() + 12);
+    assert_eq!(tokens[0], tokens[1]);
+    assert_ne!(tokens[0], tokens[2]);
+    let other_secret = RedactionConfig::with_custom(RedactionMode::Hash, Vec::new(), Some("another-secret"))?;
+    assert_ne!(other_secret.redact("a@example.com", &mut Vec::new()), tokens[0]);
+
+    // Reject mode answers 422 with the detectors and stores nothing
+    let data_dir = temp_data_dir("redaction-reject-test");
+    let config = StoreConfig {
+        redaction: RedactionConfig::with_custom(RedactionMode::Reject, Vec::new(), None)?,
+        ..StoreConfig::default()
+    };
+    let store = SPFreshVectorStore::new(&data_dir, HashEmbedder::new(64), config).await?;
+    let app = create_router(Arc::new(RwLock::new(store)));
+
+    let (status, rejected) = send(&app, "POST", "/reviews", Some(review.clone())).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(rejected["success"], false);
+    assert_eq!(rejected["data"]["detectors"], json!(["email"]));
+
+    let clean = json!({
+        "review_title": "Solid phone",
+        "review_body": "Battery lasts two days",
+        "product_id": "PHONE-1",
+        "review_rating": 5
+    });
+    let (status, bulk) = send(&app, "POST", "/reviews/bulk", Some(json!([review, clean]))).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bulk["data"][0]["status"], "rejected");
+    assert_eq!(bulk["data"][0]["detectors"], json!(["email"]));
+    assert!(bulk["data"][0]["error"].is_string());
+    assert_eq!(bulk["data"][1]["status"], "inserted");
+    let (_, stats) = send(&app, "GET", "/stats", None).await?;
+    assert_eq!(stats["data"]["total_reviews"], 1);
+
+    std::fs::remove_dir_all(&data_dir)?;
+    Ok(())
+}
@@ -5,23 +5,24 @@ use std::io::{BufRead, BufReader, Write, Read};
 use std::path::Path;
 use uuid::Uuid;
 
+use crate::embedder::Embedder;
 use crate::fastembed_service::FastEmbedService;
 use crate::models::{Review, InsertReviewRequest};
 
-pub struct VectorStore {
+pub struct VectorStore<E: Embedder = FastEmbedService> {
     data_dir: String,
     vectors: Vec<Vec<f32>>,
     reviews: Vec<Review>,
-    fastembed_service: FastEmbedService,
+    embedder: E,
 }
 
-impl VectorStore {
-    pub async fn new(data_dir: &str, fastembed_service: FastEmbedService) -> Result<Self> {
+impl<E: Embedder> VectorStore<E> {
+    pub async fn new(data_dir: &str, embedder: E) -> Result<Self> {
         let mut store = Self {
             data_dir: data_dir.to_string(),
             vectors: Vec::new(),
             reviews: Vec::new(),
-            fastembed_service,
+            embedder,
         };
 
         // Load existing data if any
@@ -68,7 +69,7 @@ impl VectorStore {
             // Generate vectors for all reviews
             for review in &self.reviews {
                 let text_to_embed = format!("{} {}", review.review_title, review.review_body);
-                let embedding = self.create_fastembed_embedding(&text_to_embed)?;
+                let embedding = self.create_embedding(&text_to_embed).await?;
                 self.vectors.push(embedding);
             }
             
@@ -89,11 +90,17 @@ impl VectorStore {
             product_id: request.product_id,
             review_rating: request.review_rating,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            language: None,
+            sentiment: None,
+            labels: Vec::new(),
+            duplicate_of: None,
+            redactions: Vec::new(),
+            status: None,
         };
 
         // Generate FastEmbed embedding
         let text_to_embed = format!("{} {}", request.review_title, request.review_body);
-        let embedding = self.create_fastembed_embedding(&text_to_embed)?;
+        let embedding = self.create_embedding(&text_to_embed).await?;
 
         // Append to in-memory storage
         self.vectors.push(embedding.clone());
@@ -131,7 +138,7 @@ impl VectorStore {
         }
 
         // Generate FastEmbed embedding for query
-        let query_vector = self.create_fastembed_embedding(query)?;
+        let query_vector = self.create_embedding(query).await?;
 
         // Calculate cosine similarities
         let mut similarities: Vec<(usize, f32)> = self.vectors
@@ -161,90 +168,9 @@ impl VectorStore {
         (self.reviews.len(), self.vectors.len())
     }
 
-    // FastEmbed-based embedding generation
-    fn create_fastembed_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        self.fastembed_service.embed_text(text)
-    }
-
-    // Legacy simple TF-IDF style embedding (kept for backward compatibility)
-    // In production, you'd use a proper embedding model
-    #[allow(dead_code)]
-    fn create_simple_embedding(&self, text: &str) -> Vec<f32> {
-        let lowercase_text = text.to_lowercase();
-        let words: Vec<&str> = lowercase_text
-            .split_whitespace()
-            .collect();
-
-        // Simplified vocabulary with only positive and negative categories
-        let positive_words = vec![
-            "good", "great", "excellent", "amazing", "wonderful", "fantastic", 
-            "love", "perfect", "best", "awesome", "outstanding", "superb", 
-            "brilliant", "magnificent", "exceptional", "marvelous", "incredible", 
-            "remarkable", "impressive", "stunning", "fabulous", "terrific",
-            "gorgeous", "beautiful", "lovely", "nice", "pleasant", "enjoyable",
-            "delightful", "charming", "attractive", "appealing", "desirable",
-            "superior", "premium", "top", "first-class", "high-quality", "fine",
-            "phenomenal", "friendly", "helpful", "patient", "knowledgeable",
-            "attentive", "cool", "affordable", "comfortable", "easy", "fast",
-            "durable", "portable", "compact", "smooth", "soft", "responsive",
-            "intuitive", "simple", "modern", "sleek", "elegant", "recommend",
-            "satisfied", "happy", "pleased", "worth", "enjoy", "appreciate",
-            "praise", "reliable", "stable", "clear", "bright", "quiet",
-            "quality", "value", "recommend", "buy", "purchase"
-        ];
-        
-        let negative_words = vec![
-            "bad", "poor", "terrible", "awful", "hate", "worst", 
-            "horrible", "disappointing", "useless", "pathetic", "disgusting",
-            "dreadful", "appalling", "shocking", "disastrous", "catastrophic",
-            "horrendous", "atrocious", "abysmal", "deplorable", "miserable",
-            "inferior", "substandard", "defective", "faulty", "broken",
-            "worthless", "garbage", "trash", "junk", "rubbish", "cheap",
-            "flimsy", "fragile", "unreliable", "unstable", "problematic",
-            "suspicious", "difficult", "slow", "expensive", "heavy", "rough",
-            "hard", "laggy", "confusing", "complex", "outdated", "bulky",
-            "disappointed", "regret", "criticize", "complain", "blame",
-            "blurry", "dark", "loud", "uncomfortable", "return", "refund"
-        ];
-
-        // Create vocabulary with only positive and negative words
-        let mut vocabulary: Vec<&str> = Vec::new();
-        vocabulary.extend(&positive_words);
-        vocabulary.extend(&negative_words);
-
-        let mut embedding = vec![0.0; vocabulary.len()];
-        
-        for (i, vocab_word) in vocabulary.iter().enumerate() {
-            let count = words.iter().filter(|&&word| word == *vocab_word).count() as f32;
-            // Simple TF with some IDF-like weighting
-            embedding[i] = count * (1.0 + (vocabulary.len() as f32 / (count + 1.0)).ln());
-        }
-
-        // Add sentiment-focused features
-        let positive_count = words.iter().filter(|&&word| positive_words.contains(&word)).count() as f32;
-        let negative_count = words.iter().filter(|&&word| negative_words.contains(&word)).count() as f32;
-        let sentiment_score = if positive_count + negative_count > 0.0 {
-            (positive_count - negative_count) / (positive_count + negative_count)
-        } else {
-            0.0
-        };
-        
-        let mut features = vec![
-            words.len() as f32 / 10.0,  // Text length feature
-            positive_count,             // Positive word count
-            negative_count,             // Negative word count
-            sentiment_score,            // Sentiment balance (-1 to 1)
-        ];
-
-        embedding.append(&mut features);
-
-        // Normalize the vector
-        let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if norm > 0.0 {
-            embedding.iter_mut().for_each(|x| *x /= norm);
-        }
-
-        embedding
+    // Embedding generation through the configured model
+    async fn create_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.embedder.embed(text).await
     }
 
     // Vector file management functions
@@ -324,7 +250,7 @@ impl VectorStore {
 }
 
 // Helper function to calculate cosine similarity
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+pub(crate) fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
     let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
     let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -1,7 +1,7 @@
 use gloo_net::http::Request;
 use serde_json;
 
-use crate::models::{ApiResponse, InsertReviewRequest, Review, SearchRequest, SearchResponse};
+use crate::models::{ApiResponse, InsertReviewRequest, Review, SearchRequest, SearchResponse, StatsResponse};
 
 const BASE_URL: &str = "";
 
@@ -64,13 +64,16 @@ pub async fn get_stats() -> Result<(usize, usize), String> {
         .map_err(|e| e.to_string())?;
 
     if response.status() == 200 {
-        let api_response: ApiResponse<(usize, usize)> = response
+        let api_response: ApiResponse<StatsResponse> = response
             .json()
             .await
             .map_err(|e| e.to_string())?;
         
         if api_response.success {
-            api_response.data.ok_or_else(|| "No data in successful response".to_string())
+            api_response
+                .data
+                .map(|stats| (stats.total_reviews, stats.total_vectors))
+                .ok_or_else(|| "No data in successful response".to_string())
         } else {
             Err(api_response.error.unwrap_or_else(|| "Unknown error".to_string()))
         }
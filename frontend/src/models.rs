@@ -36,6 +36,12 @@ pub struct ReviewWithScore {
     pub similarity_score: f32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub total_reviews: usize,
+    pub total_vectors: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,